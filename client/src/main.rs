@@ -5,5 +5,6 @@ extern crate trst_types;
 mod ui;
 
 fn main() {
+    pretty_env_logger::init();
     ui::r();
 }
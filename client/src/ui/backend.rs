@@ -0,0 +1,329 @@
+use std::{
+    future::Future,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use iced::futures::channel::oneshot;
+use trst_types::{
+    encode_frame, BackendMessage, Config, FrameDecoder, FrontendMessage, RemoteSession,
+    SessionAddress, SessionClient, SessionRequest, SessionStream,
+};
+
+use super::{program::ProgramSettings, TrstMessage};
+
+/// Name of the trster binary started for local testing.
+pub(super) const LOCAL_TRSTER: &str = "trst_trster";
+
+#[derive(Clone, Debug)]
+pub enum BackendEvent {
+    Message(BackendMessage),
+    /// Trster exited, with its error output when it failed.
+    Finished(Result<(), String>),
+}
+
+/// Session of `trster serve` kept open across runs.
+pub(super) type ServerSession = Arc<Mutex<Option<SessionClient<SessionStream>>>>;
+
+/// Where control frames of a run are written.
+enum Control {
+    /// Standard input of trster, local or on the SSH host.
+    Trster(ChildStdin),
+    /// Second handle of the session of `trster serve`.
+    Server(SessionStream),
+}
+
+impl Control {
+    fn send(&mut self, message: &FrontendMessage) -> std::io::Result<()> {
+        match self {
+            Self::Trster(stdin) => {
+                stdin.write_all(&encode_frame(message))?;
+                stdin.flush()
+            }
+            Self::Server(stream) => {
+                stream.write_all(&encode_frame(&SessionRequest::Control(message.clone())))?;
+                stream.flush()
+            }
+        }
+    }
+}
+
+/// Control of the run, messages sent before trster started wait for it.
+#[derive(Default)]
+struct ControlRoute {
+    control: Option<Control>,
+    pending: Vec<FrontendMessage>,
+}
+
+type ControlSlot = Arc<Mutex<ControlRoute>>;
+
+/// Makes the started trster reachable by control frames.
+fn attach(slot: &ControlSlot, mut control: Control) {
+    let mut route = slot.lock().unwrap();
+    for message in route.pending.drain(..) {
+        if let Err(e) = control.send(&message) {
+            log::warn!("Cannot control the run. Error = {e}");
+        }
+    }
+    route.control = Some(control);
+}
+
+/// Running trster whose frames are read by a thread of its own.
+#[derive(Clone)]
+pub(super) struct BackendConnection {
+    events: Arc<Mutex<Receiver<BackendEvent>>>,
+    control: ControlSlot,
+    paused: bool,
+}
+
+impl BackendConnection {
+    /// Runs trster on this machine, comparing the results with `baseline` when
+    /// given. The run is recorded in the history of `history_dir`.
+    pub(super) fn local(config: Config, baseline: Option<PathBuf>, history_dir: PathBuf) -> Self {
+        Self::start(move |sender, control| {
+            let configuration = ron::to_string(&config).map_err(|e| e.to_string())?;
+
+            let mut command = Command::new(LOCAL_TRSTER);
+            command
+                .args(["--format", "binary", "--configuration", &configuration])
+                .arg("--history-dir")
+                .arg(&history_dir);
+            if let Some(baseline) = &baseline {
+                command.arg("--baseline").arg(baseline);
+            }
+
+            let child = command
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("cannot start {LOCAL_TRSTER}: {e}"))?;
+
+            forward(child, sender, control)
+        })
+    }
+
+    /// Uploads the program and tests, then runs `trster` on the remote host.
+    pub(super) fn remote(session: RemoteSession, trster: String, config: Config) -> Self {
+        Self::start(move |sender, control| {
+            session
+                .upload_local(&config)
+                .map_err(|e| format!("cannot upload the tests: {e}"))?;
+
+            let child = session
+                .spawn_trster(&trster)
+                .map_err(|e| format!("cannot start trster on the remote host: {e}"))?;
+
+            forward(child, sender, control)
+        })
+    }
+
+    /// Runs the tests in a session of `trster serve`, connecting first if
+    /// there is no session yet. A session which failed is dropped. The server
+    /// compiles `source` into the program when one is given.
+    pub(super) fn server(
+        session: ServerSession,
+        address: SessionAddress,
+        token: String,
+        config: Config,
+        source: Option<ProgramSettings>,
+    ) -> Self {
+        Self::start(move |sender, control| {
+            let mut session = session.lock().unwrap();
+
+            if session.is_none() {
+                let stream = SessionStream::connect(&address)
+                    .map_err(|e| format!("cannot connect to {address:?}: {e}"))?;
+                let client = SessionClient::new(stream, &token)
+                    .map_err(|e| format!("cannot open a session: {e}"))?;
+                *session = Some(client);
+            }
+            let client = session.as_mut().unwrap();
+
+            let uploaded = match &source {
+                Some(source) => client
+                    .upload_source(
+                        &config,
+                        Path::new(&source.path),
+                        source.language,
+                        source.flags.split_whitespace().map(String::from).collect(),
+                    )
+                    .map(|(remote, _)| remote),
+                None => client.upload_local(&config),
+            };
+            let ran = match uploaded {
+                Ok(remote) => run_in_session(client, remote, sender, control)
+                    .map_err(|e| format!("testing failed: {e}")),
+                Err(e) => Err(format!("cannot prepare the tests on the server: {e}")),
+            };
+            if ran.is_err() {
+                *session = None;
+            }
+
+            ran
+        })
+    }
+
+    fn start(
+        run: impl FnOnce(&Sender<BackendEvent>, &ControlSlot) -> Result<(), String> + Send + 'static,
+    ) -> Self {
+        let (sender, events) = mpsc::channel();
+        let control = ControlSlot::default();
+
+        let slot = control.clone();
+        std::thread::spawn(move || {
+            let finished = run(&sender, &slot);
+            *slot.lock().unwrap() = ControlRoute::default();
+            let _ = sender.send(BackendEvent::Finished(finished));
+        });
+
+        Self {
+            events: Arc::new(Mutex::new(events)),
+            control,
+            paused: false,
+        }
+    }
+
+    /// Sends a control frame to the running tests.
+    pub(super) fn control(&mut self, message: FrontendMessage) {
+        match message {
+            FrontendMessage::Pause => self.paused = true,
+            FrontendMessage::Resume => self.paused = false,
+            _ => {}
+        }
+
+        let mut route = self.control.lock().unwrap();
+        match &mut route.control {
+            Some(control) => {
+                if let Err(e) = control.send(&message) {
+                    log::warn!("Cannot control the run. Error = {e}");
+                }
+            }
+            None => route.pending.push(message),
+        }
+    }
+
+    pub(super) fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Waits for the next event in the background, `None` once all were delivered.
+    pub(super) fn next(&self) -> iced::Command<TrstMessage> {
+        let events = self.events.clone();
+
+        iced::Command::perform(
+            async move {
+                blocking(move || events.lock().unwrap().recv().ok())
+                    .await
+                    .flatten()
+            },
+            TrstMessage::BackendEvent,
+        )
+    }
+}
+
+/// Runs `work` on its own thread so that it does not block the executor of
+/// the UI, the future gives `None` if the thread panicked.
+pub(super) fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> impl Future<Output = Option<T>> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+
+    async move { receiver.await.ok() }
+}
+
+/// Runs uploaded tests in a session, control frames go through a second handle.
+fn run_in_session(
+    client: &mut SessionClient<SessionStream>,
+    config: Config,
+    sender: &Sender<BackendEvent>,
+    control: &ControlSlot,
+) -> std::io::Result<()> {
+    client.start_run(config)?;
+    attach(control, Control::Server(client.control_stream()?));
+
+    client.finish_run(|message| {
+        let _ = sender.send(BackendEvent::Message(message));
+    })
+}
+
+/// Decodes frames from the standard output of trster until it exits, its
+/// standard input takes the control frames.
+fn forward(
+    mut child: Child,
+    sender: &Sender<BackendEvent>,
+    control: &ControlSlot,
+) -> Result<(), String> {
+    attach(control, Control::Trster(child.stdin.take().unwrap()));
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    // Error output is collected on the side, a full pipe would stall trster.
+    let errors = std::thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+
+    let mut decoder = FrameDecoder::<BackendMessage>::new();
+    let mut buffer = [0; 8192];
+    let mut greeted = false;
+    let mut completed = false;
+    let decoded = loop {
+        let read = match stdout.read(&mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(e) => break Err(format!("cannot read from trster: {e}")),
+        };
+
+        decoder.push(&buffer[..read]);
+        let mut failure = None;
+        while let Some(message) = decoder.next_message() {
+            match message {
+                Ok(message) if !greeted => match message.clone().accept_hello() {
+                    Ok(_) => {
+                        greeted = true;
+                        let _ = sender.send(BackendEvent::Message(message));
+                    }
+                    Err(e) => {
+                        failure = Some(e.to_string());
+                        break;
+                    }
+                },
+                Ok(message) => {
+                    // A rejected configuration ends the stream as well.
+                    completed |= matches!(
+                        message,
+                        BackendMessage::TestingProcessCompleted
+                            | BackendMessage::ConfigRejected { .. }
+                    );
+                    let _ = sender.send(BackendEvent::Message(message));
+                }
+                Err(e) if decoder.is_failed() => failure = Some(e.to_string()),
+                Err(e) => log::warn!("Skipping a frame from trster. Error = {e}"),
+            }
+        }
+        if let Some(failure) = failure {
+            let _ = child.kill();
+            break Err(failure);
+        }
+    };
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let errors = errors.join().unwrap_or_default();
+    decoded?;
+
+    // Failing tests also make trster exit with an error.
+    if status.success() || completed {
+        Ok(())
+    } else {
+        Err(format!("trster exited with {status}: {}", errors.trim()))
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use iced::{widget::text, Application, Color};
+use trst_types::{BaselineComparison, TestComparison};
+
+use super::Trst;
+
+const REGRESSION_COLOR: Color = Color::from_rgb(0.8, 0.1, 0.1);
+const IMPROVEMENT_COLOR: Color = Color::from_rgb(0.1, 0.6, 0.1);
+
+type Column<'a> = iced::widget::Column<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+type Row<'a> = iced::widget::Row<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+/// Baseline file the runs are compared with, passed to trster with `--baseline`.
+pub(super) fn baseline_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("trst")
+        .join("baseline.json")
+}
+
+/// Per-test performance deltas of the last run against its baseline.
+#[derive(Default)]
+pub(super) struct TrstComparison {
+    comparison: Option<BaselineComparison>,
+}
+
+impl TrstComparison {
+    pub(super) fn set(&mut self, comparison: BaselineComparison) {
+        self.comparison = Some(comparison);
+    }
+
+    pub(super) fn view(&self) -> Column {
+        let Some(comparison) = &self.comparison else {
+            return iced::widget::column!(text("No run was compared with a baseline yet."));
+        };
+
+        let regressions = comparison.regressions().count();
+        let header = text(format!(
+            "{} tests compared, {regressions} regressed, {} without baseline",
+            comparison.tests.len(),
+            comparison.new_tests.len()
+        ))
+        .size(24);
+
+        let rows = comparison
+            .tests
+            .iter()
+            .fold(iced::widget::column!().spacing(5), |rows, test| {
+                rows.push(Self::test_row(test))
+            });
+
+        iced::widget::column!(header, iced::widget::scrollable(rows)).spacing(15)
+    }
+
+    fn test_row(test: &TestComparison) -> Row {
+        let color = if test.regressed {
+            REGRESSION_COLOR
+        } else if test.time_delta() < 0.0 {
+            IMPROVEMENT_COLOR
+        } else {
+            Color::BLACK
+        };
+
+        let percent = test
+            .time_delta_percent()
+            .map(|percent| format!(" ({percent:+.1}%)"))
+            .unwrap_or_default();
+
+        let memory = test
+            .memory_delta()
+            .map(|delta| format!("{:+} KiB", delta / 1024))
+            .unwrap_or_else(|| "-".into());
+
+        iced::widget::row!(
+            text(&test.name).width(iced::Length::FillPortion(3)),
+            text(format!(
+                "{:.3}s -> {:.3}s",
+                test.baseline.time, test.current.time
+            ))
+            .width(iced::Length::FillPortion(3)),
+            text(format!("{:+.3}s{percent}", test.time_delta()))
+                .style(iced::theme::Text::Color(color))
+                .width(iced::Length::FillPortion(3)),
+            text(memory).width(iced::Length::FillPortion(2))
+        )
+        .spacing(10)
+    }
+}
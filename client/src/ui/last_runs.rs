@@ -0,0 +1,145 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iced::{widget::text, Application};
+use trst_types::{format_timestamp, Retention, RunHistory, RunRecord};
+
+use super::{Trst, TrstMessage};
+
+type Column<'a> = iced::widget::Column<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+/// Directory of the run history, passed to a local trster with `--history-dir`.
+/// Runs on other hosts are recorded by the client.
+pub(super) fn history_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("trst")
+        .join("history")
+}
+
+/// Past runs read from the history, newest first.
+#[derive(Default)]
+pub(super) struct TrstLastRuns {
+    runs: Vec<RunRecord>,
+    /// Positions of at most two runs chosen for comparison.
+    selected: Vec<usize>,
+    error: Option<String>,
+}
+
+impl TrstLastRuns {
+    pub(super) fn reload(&mut self) {
+        match RunHistory::open(&history_dir()).and_then(|history| history.load()) {
+            Ok(mut runs) => {
+                runs.reverse();
+                self.runs = runs;
+                self.selected.clear();
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Cannot read the run history: {e}")),
+        }
+    }
+
+    /// Applies the default retention policy to the history.
+    pub(super) fn clean_up(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let cleaned = RunHistory::open(&history_dir())
+            .and_then(|history| history.apply_retention(&Retention::default(), now));
+
+        match cleaned {
+            Ok(_) => self.reload(),
+            Err(e) => self.error = Some(format!("Cannot clean up the run history: {e}")),
+        }
+    }
+
+    pub(super) fn run(&self, index: usize) -> Option<&RunRecord> {
+        self.runs.get(index)
+    }
+
+    /// Selects or deselects a run, the oldest selection is dropped when a third run is chosen.
+    pub(super) fn toggle_selection(&mut self, index: usize) {
+        if let Some(position) = self.selected.iter().position(|selected| *selected == index) {
+            self.selected.remove(position);
+            return;
+        }
+
+        if self.selected.len() == 2 {
+            self.selected.remove(0);
+        }
+        self.selected.push(index);
+    }
+
+    /// The two selected runs, the older one first.
+    pub(super) fn selected_pair(&self) -> Option<(&RunRecord, &RunRecord)> {
+        let [first, second] = self.selected[..] else {
+            return None;
+        };
+
+        // Runs are listed newest first.
+        let (older, newer) = (first.max(second), first.min(second));
+        Some((self.runs.get(older)?, self.runs.get(newer)?))
+    }
+
+    pub(super) fn view(&self) -> Column {
+        let header = text("Last runs").size(24);
+        let clean_up = iced::widget::button(text("Remove old runs"))
+            .on_press(TrstMessage::CleanUpRuns)
+            .style(iced::theme::Button::Secondary);
+
+        let mut compare = iced::widget::button(text("Compare selected"));
+        if self.selected.len() == 2 {
+            compare = compare.on_press(TrstMessage::CompareRuns);
+        }
+
+        let buttons = iced::widget::row!(compare, clean_up).spacing(10);
+        let mut column = iced::widget::column!(header, buttons).spacing(15);
+        if let Some(error) = &self.error {
+            column = column.push(text(error));
+        }
+        if self.runs.is_empty() {
+            return column.push(text("No runs were recorded yet."));
+        }
+
+        let rows = self.runs.iter().enumerate().fold(
+            iced::widget::column!().spacing(5),
+            |rows, (index, run)| {
+                let summary = run.summary();
+                let program = run
+                    .program_hash
+                    .as_deref()
+                    .map(|hash| &hash[..hash.len().min(12)])
+                    .unwrap_or("unknown program");
+
+                let row = iced::widget::row!(
+                    text(format_timestamp(run.timestamp)).width(iced::Length::FillPortion(3)),
+                    text(format!(
+                        "{} passed, {} failed, {} not run",
+                        summary.passed(),
+                        summary.failed(),
+                        summary.not_run()
+                    ))
+                    .width(iced::Length::FillPortion(4)),
+                    text(program).width(iced::Length::FillPortion(2)),
+                    iced::widget::checkbox("Compare", self.selected.contains(&index), move |_| {
+                        TrstMessage::SelectRun(index)
+                    }),
+                    iced::widget::button(text("Open")).on_press(TrstMessage::OpenRun(index))
+                )
+                .spacing(10);
+
+                rows.push(row)
+            },
+        );
+
+        column.push(iced::widget::scrollable(rows))
+    }
+}
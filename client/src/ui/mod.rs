@@ -1,16 +1,40 @@
 use self::{
+    backend::{BackendConnection, BackendEvent, ServerSession},
+    comparison::{baseline_path, TrstComparison},
+    last_runs::{history_dir, TrstLastRuns},
     main_menu::TrstMainMenu,
-    preferences::{TrstPreferences, TrstPreferencesMessage},
+    preferences::{TestPlace, TrstPreferences, TrstPreferencesMessage},
+    program::{TrstProgram, TrstProgramMessage},
+    results::TrstResults,
+    run_comparison::TrstRunComparison,
+    tests::{TrstTests, TrstTestsMessage},
 };
 use iced::Application;
+use trst_types::{
+    format_timestamp, BackendMessage, Baseline, Config, FrontendMessage, Retention, RunHistory,
+};
 
+mod backend;
+mod comparison;
+mod last_runs;
 mod main_menu;
 mod preferences;
+mod profiles;
+mod program;
+mod results;
+mod run_comparison;
+mod test_files;
+mod tests;
 
 enum TrstFocus {
     MainMenu,
     Preferences,
     TestsSettings,
+    Program,
+    Comparison,
+    LastRuns,
+    Results,
+    RunComparison,
 }
 
 #[derive(Debug, Clone)]
@@ -19,9 +43,25 @@ enum TrstMessage {
     SwitchToPreferences,
     PreferencesMessage(TrstPreferencesMessage),
     SwitchToProgram,
+    ProgramMessage(TrstProgramMessage),
     SwitchToTests,
+    TestsMessage(TrstTestsMessage),
     ShowLastRuns,
+    /// Shows results of the run at the given position of the last runs list.
+    OpenRun(usize),
+    /// Marks the run at the given position of the last runs list for comparison.
+    SelectRun(usize),
+    CompareRuns,
+    CleanUpRuns,
+    /// Records the shown results in the baseline file.
+    SaveBaseline,
     QuitApplication,
+    /// Control frame for the running tests.
+    ControlRun(FrontendMessage),
+    /// Frame received from trster.
+    Backend(BackendMessage),
+    /// Next event of the running trster, `None` once it was the last one.
+    BackendEvent(Option<BackendEvent>),
 }
 
 impl From<TrstPreferencesMessage> for TrstMessage {
@@ -39,6 +79,22 @@ struct Trst {
     state: TrstFocus,
     main_menu: TrstMainMenu,
     preferences: TrstPreferences,
+    program: TrstProgram,
+    comparison: TrstComparison,
+    last_runs: TrstLastRuns,
+    results: TrstResults,
+    run_comparison: TrstRunComparison,
+    tests: TrstTests,
+    backend: Option<BackendConnection>,
+    server_session: ServerSession,
+    remote_run: Option<RemoteRun>,
+}
+
+/// Run on another host, whose trster cannot reach the history and baseline
+/// of this machine. Both are handled here once the run is completed.
+struct RemoteRun {
+    config: Config,
+    baseline: Option<Baseline>,
 }
 
 impl Default for Trst {
@@ -47,7 +103,114 @@ impl Default for Trst {
             state: TrstFocus::MainMenu,
             main_menu: TrstMainMenu::default(),
             preferences: TrstPreferences::default(),
+            program: TrstProgram::default(),
+            comparison: TrstComparison::default(),
+            last_runs: TrstLastRuns::default(),
+            results: TrstResults::default(),
+            run_comparison: TrstRunComparison::default(),
+            tests: TrstTests::default(),
+            backend: None,
+            server_session: ServerSession::default(),
+            remote_run: None,
+        }
+    }
+}
+
+impl Trst {
+    fn start_testing(&mut self) -> iced::Command<TrstMessage> {
+        if self.backend.is_some() {
+            return iced::Command::none();
+        }
+
+        self.results.clear("Current run".into());
+        self.state = TrstFocus::Results;
+
+        let Some(place) = self.preferences.test_place() else {
+            self.results
+                .fail("Choose the testing place in the preferences first".into());
+            return iced::Command::none();
+        };
+
+        // Tests of the preferences are all run unless others were chosen.
+        self.tests
+            .set_default_directory(self.preferences.in_test_path());
+        self.tests.rescan();
+
+        // A server compiles the source itself, it gets the source in place of the program.
+        let source = match place {
+            TestPlace::Server => self.program.source().cloned(),
+            _ => None,
+        };
+        let program = match (&source, self.program.compiled_program_path()) {
+            (Some(source), _) => Some(source.path.clone()),
+            (None, Ok(program)) => program,
+            (None, Err(e)) => {
+                self.results.fail(e);
+                return iced::Command::none();
+            }
+        };
+        let mode = self.preferences.local_mode(program);
+        let config = Config::new(
+            mode,
+            self.tests.test_list(),
+            self.program.language(),
+            self.preferences.concurrency(),
+        )
+        .with_cpu_options(self.preferences.cpu_options());
+
+        let baseline = Some(baseline_path()).filter(|path| path.exists());
+        self.remote_run = None;
+        if place != TestPlace::Local {
+            let remote_baseline = match &baseline {
+                Some(path) => match Baseline::load(path) {
+                    Ok(baseline) => Some(baseline),
+                    Err(e) => {
+                        self.results.fail(format!(
+                            "Cannot read the baseline '{}': {e}",
+                            path.display()
+                        ));
+                        return iced::Command::none();
+                    }
+                },
+                None => None,
+            };
+            self.remote_run = Some(RemoteRun {
+                config: config.clone(),
+                baseline: remote_baseline,
+            });
         }
+
+        let backend = match place {
+            TestPlace::Local => BackendConnection::local(config, baseline, history_dir()),
+            TestPlace::Ssh => match self.preferences.remote_session() {
+                Ok(session) => BackendConnection::remote(
+                    session,
+                    self.preferences.remote_trster().into(),
+                    config,
+                ),
+                Err(e) => {
+                    self.results.fail(e);
+                    return iced::Command::none();
+                }
+            },
+            TestPlace::Server => match self.preferences.server() {
+                Ok((address, token)) => BackendConnection::server(
+                    self.server_session.clone(),
+                    address,
+                    token,
+                    config,
+                    source,
+                ),
+                Err(e) => {
+                    self.results.fail(e);
+                    return iced::Command::none();
+                }
+            },
+        };
+        let next = backend.next();
+        self.backend = Some(backend);
+
+        next
     }
 }
 
@@ -61,7 +224,13 @@ impl Application for Trst {
     type Flags = ();
 
     fn new(_flags: Self::Flags) -> (Self, iced::Command<Self::Message>) {
-        (Self::default(), iced::Command::none())
+        let mut trst = Self {
+            preferences: TrstPreferences::load(),
+            ..Self::default()
+        };
+        trst.program.apply(trst.preferences.program());
+
+        (trst, iced::Command::none())
     }
 
     fn title(&self) -> String {
@@ -69,10 +238,128 @@ impl Application for Trst {
     }
 
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
-        println!("{:?}", message);
+        log::trace!("{message:?}");
 
         match message {
-            TrstMessage::PreferencesMessage(msg) => self.preferences.update(msg),
+            TrstMessage::StartTesting => return self.start_testing(),
+            TrstMessage::BackendEvent(Some(BackendEvent::Message(message))) => {
+                let command = self.update(TrstMessage::Backend(message));
+                let next = self.backend.as_ref().map(BackendConnection::next);
+
+                return iced::Command::batch(next.into_iter().chain([command]));
+            }
+            TrstMessage::BackendEvent(Some(BackendEvent::Finished(finished))) => {
+                if let Err(e) = finished {
+                    self.results.fail(e);
+                    self.state = TrstFocus::Results;
+                }
+                self.backend = None;
+            }
+            TrstMessage::BackendEvent(None) => self.backend = None,
+            TrstMessage::ControlRun(message) => {
+                if let Some(backend) = &mut self.backend {
+                    backend.control(message);
+                }
+            }
+            TrstMessage::PreferencesMessage(msg) => {
+                self.preferences.update(msg);
+                // Another profile may have been chosen.
+                self.program.apply(self.preferences.program());
+            }
+            TrstMessage::SwitchToPreferences => self.state = TrstFocus::Preferences,
+            TrstMessage::SwitchToTests => {
+                self.tests
+                    .set_default_directory(self.preferences.in_test_path());
+                self.tests.set_expected_directories(
+                    self.preferences.out_test_path(),
+                    self.preferences.err_test_path(),
+                );
+                self.tests.rescan();
+                self.state = TrstFocus::TestsSettings;
+            }
+            TrstMessage::TestsMessage(msg) => return self.tests.update(msg),
+            TrstMessage::SwitchToProgram => self.state = TrstFocus::Program,
+            TrstMessage::ProgramMessage(msg) => {
+                let command = self.program.update(msg);
+                self.preferences.set_program(self.program.settings());
+
+                return command;
+            }
+            TrstMessage::ShowLastRuns => {
+                self.last_runs.reload();
+                self.state = TrstFocus::LastRuns;
+            }
+            TrstMessage::OpenRun(index) => {
+                if let Some(run) = self.last_runs.run(index) {
+                    let title = format!("Run of {}", format_timestamp(run.timestamp));
+                    self.results.show(title, run.results.clone());
+                    self.state = TrstFocus::Results;
+                }
+            }
+            TrstMessage::SelectRun(index) => self.last_runs.toggle_selection(index),
+            TrstMessage::CompareRuns => {
+                if let Some((before, after)) = self.last_runs.selected_pair() {
+                    self.run_comparison.set(before, after);
+                    self.state = TrstFocus::RunComparison;
+                }
+            }
+            TrstMessage::CleanUpRuns => self.last_runs.clean_up(),
+            TrstMessage::SaveBaseline => {
+                let path = baseline_path();
+                let saved = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| Baseline::save_results(&path, self.results.results()));
+
+                match saved {
+                    Ok(()) => self.results.notify("Saved as the baseline".into()),
+                    Err(e) => self.results.fail(format!(
+                        "Cannot save the baseline to '{}': {e}",
+                        path.display()
+                    )),
+                }
+            }
+            TrstMessage::Backend(BackendMessage::TestCompleted { result }) => {
+                self.results.push(result);
+            }
+            TrstMessage::Backend(BackendMessage::ConfigRejected {
+                description,
+                issues,
+            }) => {
+                let error = if issues.is_empty() {
+                    description
+                } else {
+                    let issues: Vec<_> = issues.iter().map(|issue| format!("- {issue}")).collect();
+                    format!("The configuration was rejected:\n{}", issues.join("\n"))
+                };
+                self.results.fail(error);
+                self.state = TrstFocus::Results;
+            }
+            TrstMessage::Backend(BackendMessage::TestingProcessCompleted) => {
+                let Some(run) = self.remote_run.take() else {
+                    return iced::Command::none();
+                };
+
+                let recorded = RunHistory::open(&history_dir()).and_then(|history| {
+                    history.record(&run.config, self.results.results(), &Retention::default())
+                });
+                if let Err(e) = recorded {
+                    self.results
+                        .fail(format!("Cannot record the run in the history: {e}"));
+                }
+
+                if let Some(baseline) = run.baseline {
+                    let comparison = baseline
+                        .compare(self.results.results(), run.config.regression_thresholds());
+                    return self.update(TrstMessage::Backend(BackendMessage::BaselineComparison {
+                        comparison,
+                    }));
+                }
+            }
+            TrstMessage::Backend(BackendMessage::BaselineComparison { comparison }) => {
+                self.comparison.set(comparison);
+                self.state = TrstFocus::Comparison;
+            }
             _ => {}
         }
 
@@ -80,11 +367,19 @@ impl Application for Trst {
     }
 
     fn view(&self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
-        let preferences_col =
-            iced::widget::column!(self.preferences.view()).width(iced::Length::FillPortion(9));
+        let content = match self.state {
+            TrstFocus::Comparison => self.comparison.view(),
+            TrstFocus::LastRuns => self.last_runs.view(),
+            TrstFocus::Results => self.results.view(self.backend.as_ref()),
+            TrstFocus::RunComparison => self.run_comparison.view(),
+            TrstFocus::TestsSettings => self.tests.view(),
+            TrstFocus::Program => self.program.view(),
+            _ => iced::widget::column!(self.preferences.view()),
+        };
+        let content_col = content.width(iced::Length::FillPortion(9));
 
         let rows = iced::widget::row!(
-            preferences_col,
+            content_col,
             self.main_menu.view().width(iced::Length::FillPortion(2))
         )
         .padding(12);
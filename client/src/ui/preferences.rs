@@ -1,16 +1,26 @@
+use std::path::{Path, PathBuf};
+
 use iced::{
     widget::{Container, Row},
     Element, Sandbox,
 };
 use iced_native::{Renderer, Widget};
+use serde::{Deserialize, Serialize};
 
-use super::TrstMessage;
-use trst_types::RunningMode;
+use super::{
+    profiles::{Profile, ProfileStore},
+    program::ProgramSettings,
+    TrstMessage,
+};
+use trst_types::{
+    Concurrency, CpuOptions, RemoteSession, RunningMode, SessionAddress, SshSettings, Transport,
+};
 
-#[derive(Clone, PartialEq, Eq, Copy, Debug)]
+#[derive(Clone, PartialEq, Eq, Copy, Debug, Serialize, Deserialize)]
 pub enum TestPlace {
     Local,
     Ssh,
+    Server,
 }
 
 impl TestPlace {
@@ -18,40 +28,604 @@ impl TestPlace {
         match self {
             Self::Local => "Local instance",
             Self::Ssh => "Ssh instance",
+            Self::Server => "Trster server",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Copy, Debug, Serialize, Deserialize)]
+pub enum ConcurrencyMode {
+    Disabled,
+    Fixed,
+    Auto,
+}
+
+impl ConcurrencyMode {
+    fn desc(&self) -> &'static str {
+        match self {
+            Self::Disabled => "Disabled",
+            Self::Fixed => "Fixed number of tests",
+            Self::Auto => "All cores except reserved ones",
+        }
+    }
+}
+
+/// Paths of `RunningMode::Local` set in the preferences.
+#[derive(Clone, PartialEq, Eq, Copy, Debug)]
+pub enum LocalPath {
+    Input,
+    Output,
+    Error,
+    Program,
+}
+
+impl LocalPath {
+    const ALL: [LocalPath; 4] = [Self::Input, Self::Output, Self::Error, Self::Program];
+
+    fn desc(&self) -> &'static str {
+        match self {
+            Self::Input => "Directory of test inputs (.in)",
+            Self::Output => "Directory of expected outputs (.out)",
+            Self::Error => "Directory of expected error outputs (.err)",
+            Self::Program => "Compiled program",
+        }
+    }
+
+    fn is_directory(&self) -> bool {
+        !matches!(self, Self::Program)
+    }
+}
+
+fn labeled_input<'a>(
+    label: &str,
+    placeholder: &str,
+    value: &str,
+    on_change: fn(String) -> TrstMessage,
+) -> Element<'a, TrstMessage> {
+    iced::widget::column![
+        iced::widget::text(label).size(15),
+        iced::widget::TextInput::new(placeholder, value, on_change)
+    ]
+    .spacing(3)
+    .into()
+}
+
+/// Status shown below a local path. It reads the file system, so it is
+/// computed when the path changes and not while drawing.
+fn local_path_status(which: LocalPath, path: &str) -> String {
+    if path.is_empty() {
+        "Not set".to_string()
+    } else if !Path::new(path).exists() {
+        "Does not exist".to_string()
+    } else if which.is_directory() != Path::new(path).is_dir() {
+        if which.is_directory() {
+            "Not a directory".to_string()
+        } else {
+            "Not a file".to_string()
+        }
+    } else if which == LocalPath::Input {
+        match count_tests(path) {
+            Some(1) => "1 test found".to_string(),
+            Some(count) => format!("{count} tests found"),
+            None => "Cannot be read".to_string(),
         }
+    } else {
+        "Found".to_string()
     }
 }
 
+/// Number of `.in` files in `dir`, if it can be read.
+fn count_tests(dir: &str) -> Option<usize> {
+    let count = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "in"))
+        .count();
+
+    Some(count)
+}
+
 pub(super) struct TrstPreferences {
-    concurrency: bool,
+    concurrency: ConcurrencyMode,
+    threads: u32,
+    reserve: u32,
+    pin_tests: bool,
+    skip_smt_siblings: bool,
+    available_cores: u32,
     test_place: Option<TestPlace>,
-    git_address: String
+    git_address: String,
+    in_test_path: String,
+    out_test_path: String,
+    err_test_path: String,
+    compiled_program_path: String,
+    /// Statuses of the local paths, indexed by `LocalPath as usize`.
+    local_path_statuses: [String; 4],
+    ssh: SshSettings,
+    transport_command: String,
+    remote_dir: String,
+    remote_trster: String,
+    server_address: String,
+    server_token: String,
+    program: ProgramSettings,
+
+    profiles: ProfileStore,
+    profiles_path: PathBuf,
+    new_profile_name: String,
+    /// Last failure of writing the preferences file.
+    error: Option<String>,
+    /// Why the preferences file could not be read when the application started.
+    load_error: Option<String>,
+    /// Set when an unreadable preferences file could not be moved aside, it is
+    /// never overwritten then.
+    read_only: bool,
 }
 
 #[derive(Clone, Debug)]
 pub enum TrstPreferencesMessage {
-    ConcurrencySelected(bool),
+    ConcurrencySelected(ConcurrencyMode),
+    ThreadsChange(u32),
+    ReserveChange(u32),
+    PinTestsToggle(bool),
+    SkipSmtSiblingsToggle(bool),
     TestPlaceSelected(TestPlace),
-    GitAddressChange(String)
+    GitAddressChange(String),
+    LocalPathChange(LocalPath, String),
+    /// Chooses the path with a native file dialog.
+    PickLocalPath(LocalPath),
+    SshHostChange(String),
+    SshUserChange(String),
+    SshPortChange(String),
+    SshKeyChange(String),
+    PickSshKey,
+    TransportCommandChange(String),
+    RemoteDirChange(String),
+    RemoteTrsterChange(String),
+    ServerAddressChange(String),
+    ServerTokenChange(String),
+    ProfileSelected(String),
+    NewProfileNameChange(String),
+    CreateProfile,
+    DeleteProfile,
 }
 
 impl Default for TrstPreferences {
     fn default() -> Self {
-        Self {
-            concurrency: false,
+        let available_cores = std::thread::available_parallelism()
+            .map(|cores| cores.get() as u32)
+            .unwrap_or(1);
+
+        let mut preferences = Self {
+            concurrency: ConcurrencyMode::Disabled,
+            threads: 1,
+            reserve: 1.min(available_cores - 1),
+            pin_tests: true,
+            skip_smt_siblings: false,
+            available_cores,
             test_place: None,
-            git_address: String::new()
-        }
+            git_address: String::new(),
+            in_test_path: String::new(),
+            out_test_path: String::new(),
+            err_test_path: String::new(),
+            compiled_program_path: String::new(),
+            local_path_statuses: Default::default(),
+            ssh: SshSettings::default(),
+            transport_command: String::new(),
+            remote_dir: String::new(),
+            remote_trster: String::new(),
+            server_address: String::new(),
+            server_token: String::new(),
+            program: ProgramSettings::default(),
+            profiles: ProfileStore::default(),
+            profiles_path: ProfileStore::path(),
+            new_profile_name: String::new(),
+            error: None,
+            load_error: None,
+            read_only: false,
+        };
+        preferences.apply(&Profile::default());
+
+        preferences
     }
 }
 
 impl TrstPreferences {
+    /// Preferences of the profile in use, read from the preferences file.
+    pub fn load() -> Self {
+        let mut preferences = Self::default();
+
+        match ProfileStore::load(&preferences.profiles_path) {
+            Ok(profiles) => preferences.profiles = profiles,
+            // Keep the unreadable file, saving the defaults would replace it.
+            Err(e) => match ProfileStore::back_up(&preferences.profiles_path) {
+                Ok(backup) => {
+                    preferences.load_error =
+                        Some(format!("{e}, it was moved to '{}'", backup.display()));
+                }
+                Err(backup_error) => {
+                    preferences.load_error =
+                        Some(format!("{e}, changes are not saved: {backup_error}"));
+                    preferences.read_only = true;
+                }
+            },
+        }
+        let profile = preferences.profiles.active().clone();
+        preferences.apply(&profile);
+
+        preferences
+    }
+
+    fn profile(&self) -> Profile {
+        Profile {
+            concurrency: self.concurrency,
+            threads: self.threads,
+            reserve: self.reserve,
+            pin_tests: self.pin_tests,
+            skip_smt_siblings: self.skip_smt_siblings,
+            test_place: self.test_place,
+            git_address: self.git_address.clone(),
+            in_test_path: self.in_test_path.clone(),
+            out_test_path: self.out_test_path.clone(),
+            err_test_path: self.err_test_path.clone(),
+            compiled_program_path: self.compiled_program_path.clone(),
+            ssh: self.ssh.clone(),
+            transport_command: self.transport_command.clone(),
+            remote_dir: self.remote_dir.clone(),
+            remote_trster: self.remote_trster.clone(),
+            server_address: self.server_address.clone(),
+            server_token: self.server_token.clone(),
+            program: self.program.clone(),
+        }
+    }
+
+    /// Takes over a profile, keeping core counts within what this machine has.
+    fn apply(&mut self, profile: &Profile) {
+        self.concurrency = profile.concurrency;
+        self.threads = profile.threads.clamp(1, self.available_cores);
+        self.reserve = profile.reserve.min(self.available_cores - 1);
+        self.pin_tests = profile.pin_tests;
+        self.skip_smt_siblings = profile.skip_smt_siblings;
+        self.test_place = profile.test_place;
+        self.git_address = profile.git_address.clone();
+        self.in_test_path = profile.in_test_path.clone();
+        self.out_test_path = profile.out_test_path.clone();
+        self.err_test_path = profile.err_test_path.clone();
+        self.compiled_program_path = profile.compiled_program_path.clone();
+        for which in LocalPath::ALL {
+            self.refresh_local_path_status(which);
+        }
+        self.ssh = profile.ssh.clone();
+        self.transport_command = profile.transport_command.clone();
+        self.remote_dir = profile.remote_dir.clone();
+        self.remote_trster = profile.remote_trster.clone();
+        self.server_address = profile.server_address.clone();
+        self.server_token = profile.server_token.clone();
+        self.program = profile.program.clone();
+    }
+
+    /// Program of the profile in use.
+    pub(super) fn program(&self) -> &ProgramSettings {
+        &self.program
+    }
+
+    /// Saves a change of the program in the profile in use.
+    pub(super) fn set_program(&mut self, program: &ProgramSettings) {
+        if self.program != *program {
+            self.program = program.clone();
+            self.save();
+        }
+    }
+
+    pub(super) fn test_place(&self) -> Option<TestPlace> {
+        self.test_place
+    }
+
+    pub(super) fn concurrency(&self) -> Concurrency {
+        match self.concurrency {
+            ConcurrencyMode::Disabled => Concurrency::Disabled,
+            ConcurrencyMode::Fixed => Concurrency::Enabled(self.threads.into()),
+            ConcurrencyMode::Auto => Concurrency::Auto {
+                reserve: self.reserve.into(),
+            },
+        }
+    }
+
+    pub(super) fn cpu_options(&self) -> CpuOptions {
+        CpuOptions::new(self.pin_tests, self.skip_smt_siblings)
+    }
+
+    /// Working directory on the SSH host, reached with the transport command if one is set.
+    pub(super) fn remote_session(&self) -> Result<RemoteSession, String> {
+        let transport = match Transport::parse(&self.transport_command) {
+            Some(transport) => transport,
+            None if self.ssh.host.trim().is_empty() => {
+                return Err("Set the SSH host or a transport command first".into())
+            }
+            None => Transport::ssh(&self.ssh),
+        };
+
+        Ok(RemoteSession::new(transport, self.remote_dir.clone()))
+    }
+
+    pub(super) fn remote_trster(&self) -> &str {
+        &self.remote_trster
+    }
+
+    /// Address and token of `trster serve`.
+    pub(super) fn server(&self) -> Result<(SessionAddress, String), String> {
+        let address = self
+            .server_address
+            .parse()
+            .map_err(|e| format!("Invalid server address: {e}"))?;
+
+        Ok((address, self.server_token.clone()))
+    }
+
+    fn local_path(&self, which: LocalPath) -> &String {
+        match which {
+            LocalPath::Input => &self.in_test_path,
+            LocalPath::Output => &self.out_test_path,
+            LocalPath::Error => &self.err_test_path,
+            LocalPath::Program => &self.compiled_program_path,
+        }
+    }
+
+    fn refresh_local_path_status(&mut self, which: LocalPath) {
+        self.local_path_statuses[which as usize] = local_path_status(which, self.local_path(which));
+    }
+
+    fn local_path_mut(&mut self, which: LocalPath) -> &mut String {
+        match which {
+            LocalPath::Input => &mut self.in_test_path,
+            LocalPath::Output => &mut self.out_test_path,
+            LocalPath::Error => &mut self.err_test_path,
+            LocalPath::Program => &mut self.compiled_program_path,
+        }
+    }
+
+    pub(super) fn in_test_path(&self) -> &str {
+        &self.in_test_path
+    }
+
+    pub(super) fn out_test_path(&self) -> &str {
+        &self.out_test_path
+    }
+
+    pub(super) fn err_test_path(&self) -> &str {
+        &self.err_test_path
+    }
+
+    /// Local mode with the paths set here, `compiled_program_path` replaces
+    /// the program path of the preferences when given.
+    pub(super) fn local_mode(&self, compiled_program_path: Option<String>) -> RunningMode {
+        RunningMode::Local {
+            in_test_path: self.in_test_path.clone(),
+            out_test_path: self.out_test_path.clone(),
+            err_test_path: self.err_test_path.clone(),
+            compiled_program_path: compiled_program_path
+                .unwrap_or_else(|| self.compiled_program_path.clone()),
+        }
+    }
+
+    fn pick_local_path(&mut self, which: LocalPath) -> bool {
+        let current = Path::new(self.local_path(which));
+        let mut dialog = rfd::FileDialog::new().set_title(which.desc());
+        if let Some(dir) = Some(current)
+            .filter(|path| path.is_dir())
+            .or_else(|| current.parent().filter(|dir| dir.is_dir()))
+        {
+            dialog = dialog.set_directory(dir);
+        }
+
+        let picked = if which.is_directory() {
+            dialog.pick_folder()
+        } else {
+            dialog.pick_file()
+        };
+
+        let Some(path) = picked else {
+            return false;
+        };
+        *self.local_path_mut(which) = path.to_string_lossy().into_owned();
+
+        true
+    }
+
+    /// Stores the current preferences under the active profile and writes the file.
+    fn save(&mut self) {
+        if self.read_only {
+            return;
+        }
+
+        let active = self.profiles.active.clone();
+        self.profiles.profiles.insert(active, self.profile());
+        self.error = self.profiles.save(&self.profiles_path).err();
+    }
+
     pub fn update(&mut self, msg: TrstPreferencesMessage) {
         match msg {
-            TrstPreferencesMessage::ConcurrencySelected(val) => self.concurrency = val,
+            TrstPreferencesMessage::ConcurrencySelected(mode) => self.concurrency = mode,
+            TrstPreferencesMessage::ThreadsChange(threads) => self.threads = threads,
+            TrstPreferencesMessage::ReserveChange(reserve) => self.reserve = reserve,
+            TrstPreferencesMessage::PinTestsToggle(val) => self.pin_tests = val,
+            TrstPreferencesMessage::SkipSmtSiblingsToggle(val) => self.skip_smt_siblings = val,
             TrstPreferencesMessage::TestPlaceSelected(place) => self.test_place = Some(place),
             TrstPreferencesMessage::GitAddressChange(address) => self.git_address = address,
+            TrstPreferencesMessage::LocalPathChange(which, path) => {
+                *self.local_path_mut(which) = path;
+                self.refresh_local_path_status(which);
+            }
+            TrstPreferencesMessage::PickLocalPath(which) => {
+                if !self.pick_local_path(which) {
+                    return;
+                }
+                self.refresh_local_path_status(which);
+            }
+            TrstPreferencesMessage::SshHostChange(host) => self.ssh.host = host,
+            TrstPreferencesMessage::SshUserChange(user) => self.ssh.user = user,
+            TrstPreferencesMessage::SshPortChange(port) => {
+                if port.is_empty() {
+                    self.ssh.port = None;
+                } else if let Ok(port) = port.parse() {
+                    self.ssh.port = Some(port);
+                } else {
+                    return;
+                }
+            }
+            TrstPreferencesMessage::SshKeyChange(key) => {
+                self.ssh.key = Some(key).filter(|key| !key.is_empty())
+            }
+            TrstPreferencesMessage::PickSshKey => {
+                let mut dialog = rfd::FileDialog::new().set_title("SSH private key");
+                if let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) {
+                    dialog = dialog.set_directory(ssh_dir);
+                }
+                let Some(key) = dialog.pick_file() else {
+                    return;
+                };
+                self.ssh.key = Some(key.to_string_lossy().into_owned());
+            }
+            TrstPreferencesMessage::TransportCommandChange(command) => {
+                self.transport_command = command
+            }
+            TrstPreferencesMessage::RemoteDirChange(dir) => self.remote_dir = dir,
+            TrstPreferencesMessage::RemoteTrsterChange(trster) => self.remote_trster = trster,
+            TrstPreferencesMessage::ServerAddressChange(address) => self.server_address = address,
+            TrstPreferencesMessage::ServerTokenChange(token) => self.server_token = token,
+            TrstPreferencesMessage::ProfileSelected(name) => {
+                let Some(profile) = self.profiles.profiles.get(&name).cloned() else {
+                    return;
+                };
+                self.profiles.active = name;
+                self.apply(&profile);
+            }
+            TrstPreferencesMessage::NewProfileNameChange(name) => {
+                // Nothing to save until the profile is created.
+                self.new_profile_name = name;
+                return;
+            }
+            TrstPreferencesMessage::CreateProfile => {
+                let name = self.new_profile_name.trim().to_string();
+                // An existing profile is never overwritten by a new one.
+                if name.is_empty() || self.profiles.profiles.contains_key(&name) {
+                    return;
+                }
+                // The new profile starts as a copy of the current preferences.
+                self.profiles.active = name;
+                self.new_profile_name.clear();
+            }
+            TrstPreferencesMessage::DeleteProfile => {
+                if self.profiles.profiles.len() < 2 {
+                    return;
+                }
+                self.profiles.profiles.remove(&self.profiles.active);
+                self.profiles.active = self.profiles.profiles.keys().next().unwrap().clone();
+                let profile = self.profiles.active().clone();
+                self.apply(&profile);
+            }
+        }
+
+        self.save();
+    }
+
+    fn generate_profiles_box(&self) -> Container<TrstMessage> {
+        let picker = iced::widget::pick_list(
+            self.profiles.names(),
+            Some(self.profiles.active.clone()),
+            |name| TrstPreferencesMessage::ProfileSelected(name).into(),
+        );
+
+        let mut delete = iced::widget::button(iced::widget::text("Delete profile"))
+            .style(iced::theme::Button::Destructive);
+        if self.profiles.profiles.len() > 1 {
+            delete = delete.on_press(TrstPreferencesMessage::DeleteProfile.into());
+        }
+
+        let name = self.new_profile_name.trim();
+        let taken = self.profiles.profiles.contains_key(name);
+        let mut create = iced::widget::button(iced::widget::text("Save as new profile"));
+        if !name.is_empty() && !taken {
+            create = create.on_press(TrstPreferencesMessage::CreateProfile.into());
+        }
+
+        let new_profile = iced::widget::row![
+            iced::widget::TextInput::new("New profile name", &self.new_profile_name, |val| {
+                TrstPreferencesMessage::NewProfileNameChange(val).into()
+            })
+            .on_submit(TrstPreferencesMessage::CreateProfile.into()),
+            create
+        ]
+        .spacing(10);
+
+        let mut content = iced::widget::column![
+            iced::widget::text("Profile")
+                .size(40)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+                .width(iced::Length::Fill),
+            iced::widget::row![picker, delete].spacing(10),
+            new_profile
+        ]
+        .spacing(15);
+        if taken {
+            content = content.push(
+                iced::widget::text(format!("A profile named '{name}' already exists")).size(15),
+            );
+        }
+        if let Some(error) = &self.load_error {
+            content = content.push(
+                iced::widget::text(format!("Preferences could not be loaded: {error}")).size(15),
+            );
         }
+        if let Some(error) = &self.error {
+            content = content.push(iced::widget::text(format!("Preferences are not saved: {error}")).size(15));
+        }
+
+        let appearance = iced::theme::Container::Custom(|_| {
+            let mut app = iced::widget::container::Appearance::default();
+            app.border_color = iced::Color::BLACK;
+            app.background = None;
+            app.border_width = 3.0;
+            app.border_radius = 2.0;
+
+            app
+        });
+
+        iced::widget::container(content.padding(15))
+            .style(appearance)
+            .width(iced::Length::Fill)
+    }
+
+    fn generate_concurrency_amount(&self) -> Element<TrstMessage> {
+        let (label, value, range, on_change): (String, u32, _, fn(u32) -> TrstMessage) =
+            match self.concurrency {
+                ConcurrencyMode::Disabled => {
+                    return iced::widget::text("Tests are run one after another")
+                        .size(15)
+                        .into()
+                }
+                ConcurrencyMode::Fixed => (
+                    format!("Tests run at once: {}", self.threads),
+                    self.threads,
+                    1..=self.available_cores,
+                    |val| TrstPreferencesMessage::ThreadsChange(val).into(),
+                ),
+                ConcurrencyMode::Auto => (
+                    format!(
+                        "Reserved cores: {} (tests run at once: {})",
+                        self.reserve,
+                        self.available_cores.saturating_sub(self.reserve).max(1)
+                    ),
+                    self.reserve,
+                    0..=self.available_cores - 1,
+                    |val| TrstPreferencesMessage::ReserveChange(val).into(),
+                ),
+            };
+
+        iced::widget::column![
+            iced::widget::text(label).size(15),
+            iced::widget::slider(range, value, on_change)
+        ]
+        .spacing(3)
+        .into()
     }
 
     fn generate_concurrency_box(&self) -> Container<TrstMessage> {
@@ -60,18 +634,33 @@ impl TrstPreferences {
                 .size(40)
                 .horizontal_alignment(iced::alignment::Horizontal::Center)
                 .width(iced::Length::Fill),
-            iced::widget::column(vec![
-                iced::Element::from(iced::widget::radio(
-                    "Enabled",
-                    true,
+            iced::widget::column(
+                [
+                    ConcurrencyMode::Disabled,
+                    ConcurrencyMode::Fixed,
+                    ConcurrencyMode::Auto
+                ]
+                .into_iter()
+                .map(|mode| iced::Element::from(iced::widget::radio(
+                    mode.desc(),
+                    mode,
                     Some(self.concurrency),
                     |val| TrstPreferencesMessage::ConcurrencySelected(val).into()
+                )))
+                .collect()
+            )
+            .spacing(3),
+            self.generate_concurrency_amount(),
+            iced::widget::column(vec![
+                iced::Element::from(iced::widget::checkbox(
+                    "Pin every test to its own core",
+                    self.pin_tests,
+                    |val| TrstPreferencesMessage::PinTestsToggle(val).into()
                 )),
-                iced::Element::from(iced::widget::radio(
-                    "Disabled",
-                    false,
-                    Some(self.concurrency),
-                    |val| TrstPreferencesMessage::ConcurrencySelected(val).into()
+                iced::Element::from(iced::widget::checkbox(
+                    "Use one logical CPU per physical core",
+                    self.skip_smt_siblings,
+                    |val| TrstPreferencesMessage::SkipSmtSiblingsToggle(val).into()
                 ))
             ])
             .spacing(3)
@@ -111,6 +700,12 @@ impl TrstPreferences {
                     TestPlace::Ssh,
                     self.test_place,
                     |val| TrstPreferencesMessage::TestPlaceSelected(val).into()
+                )),
+                iced::Element::from(iced::widget::radio(
+                    TestPlace::Server.desc(),
+                    TestPlace::Server,
+                    self.test_place,
+                    |val| TrstPreferencesMessage::TestPlaceSelected(val).into()
                 ))
             ])
             .spacing(3)
@@ -169,20 +764,177 @@ impl TrstPreferences {
             .width(iced::Length::Fill)
     }
 
+    fn generate_ssh_settings_box(&self) -> Container<TrstMessage> {
+        let port = self.ssh.port.map(|port| port.to_string()).unwrap_or_default();
+        let key = self.ssh.key.clone().unwrap_or_default();
+
+        let content = iced::widget::column![
+            iced::widget::text("SSH host")
+                .size(40)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+                .width(iced::Length::Fill),
+            labeled_input("Host", "example.org", &self.ssh.host, |val| {
+                TrstPreferencesMessage::SshHostChange(val).into()
+            }),
+            labeled_input("User", "Login of the SSH configuration", &self.ssh.user, |val| {
+                TrstPreferencesMessage::SshUserChange(val).into()
+            }),
+            labeled_input("Port", "22", &port, |val| {
+                TrstPreferencesMessage::SshPortChange(val).into()
+            }),
+            iced::widget::column![
+                iced::widget::text("Private key").size(15),
+                iced::widget::row![
+                    iced::widget::TextInput::new("Agent and SSH configuration", &key, |val| {
+                        TrstPreferencesMessage::SshKeyChange(val).into()
+                    }),
+                    iced::widget::button(iced::widget::text("Browse"))
+                        .on_press(TrstPreferencesMessage::PickSshKey.into())
+                        .style(iced::theme::Button::Secondary)
+                ]
+                .spacing(10)
+            ]
+            .spacing(3),
+            labeled_input("Remote working directory", "trst", &self.remote_dir, |val| {
+                TrstPreferencesMessage::RemoteDirChange(val).into()
+            }),
+            labeled_input("Remote trster command", "trst_trster", &self.remote_trster, |val| {
+                TrstPreferencesMessage::RemoteTrsterChange(val).into()
+            }),
+            labeled_input(
+                "Transport command, replaces ssh when set",
+                "sh -c",
+                &self.transport_command,
+                |val| TrstPreferencesMessage::TransportCommandChange(val).into()
+            )
+        ]
+        .spacing(15);
+        let content = match self.remote_session() {
+            Ok(_) => content,
+            Err(e) => content.push(iced::widget::text(e).size(15)),
+        };
+
+        let appearance = iced::theme::Container::Custom(|_| {
+            let mut app = iced::widget::container::Appearance::default();
+            app.border_color = iced::Color::BLACK;
+            app.background = None;
+            app.border_width = 3.0;
+            app.border_radius = 2.0;
+
+            app
+        });
+
+        iced::widget::container(content.padding(15))
+            .style(appearance)
+            .width(iced::Length::Fill)
+    }
+
+    fn generate_server_settings_box(&self) -> Container<TrstMessage> {
+        let content = iced::widget::column![
+            iced::widget::text("Trster server")
+                .size(40)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+                .width(iced::Length::Fill),
+            iced::widget::text("Started on the server with `trst_trster serve --listen <address> --token-file <file>`.")
+                .size(15)
+                .horizontal_alignment(iced::alignment::Horizontal::Left)
+                .width(iced::Length::Fill),
+            labeled_input("Address", "lab.example.org:7070 or unix:/run/trster.sock", &self.server_address, |val| {
+                TrstPreferencesMessage::ServerAddressChange(val).into()
+            }),
+            iced::widget::column![
+                iced::widget::text("Token").size(15),
+                iced::widget::TextInput::new("Shared token", &self.server_token, |val| {
+                    TrstPreferencesMessage::ServerTokenChange(val).into()
+                })
+                .password()
+            ]
+            .spacing(3)
+        ]
+        .spacing(15);
+
+        let appearance = iced::theme::Container::Custom(|_| {
+            let mut app = iced::widget::container::Appearance::default();
+            app.border_color = iced::Color::BLACK;
+            app.background = None;
+            app.border_width = 3.0;
+            app.border_radius = 2.0;
+
+            app
+        });
+
+        iced::widget::container(content.padding(15))
+            .style(appearance)
+            .width(iced::Length::Fill)
+    }
+
+    fn generate_local_paths_box(&self) -> Container<TrstMessage> {
+        let paths = LocalPath::ALL.into_iter().map(|which| {
+            let path = self.local_path(which);
+            let status = &self.local_path_statuses[which as usize];
+
+            iced::Element::from(
+                iced::widget::column![
+                    iced::widget::text(which.desc()).size(15),
+                    iced::widget::row![
+                        iced::widget::TextInput::new("Path", path, move |val| {
+                            TrstPreferencesMessage::LocalPathChange(which, val).into()
+                        }),
+                        iced::widget::button(iced::widget::text("Browse"))
+                            .on_press(TrstPreferencesMessage::PickLocalPath(which).into())
+                            .style(iced::theme::Button::Secondary)
+                    ]
+                    .spacing(10),
+                    iced::widget::text(status).size(15)
+                ]
+                .spacing(3),
+            )
+        });
+
+        let content = iced::widget::column![
+            iced::widget::text("Local testing")
+                .size(40)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+                .width(iced::Length::Fill),
+            iced::widget::column(paths.collect()).spacing(10)
+        ]
+        .spacing(15);
+
+        let appearance = iced::theme::Container::Custom(|_| {
+            let mut app = iced::widget::container::Appearance::default();
+            app.border_color = iced::Color::BLACK;
+            app.background = None;
+            app.border_width = 3.0;
+            app.border_radius = 2.0;
+
+            app
+        });
+
+        iced::widget::container(content.padding(15))
+            .style(appearance)
+            .width(iced::Length::Fill)
+    }
+
     pub fn view(&self) -> Element<TrstMessage> {
+        let profiles_box = self.generate_profiles_box();
         let concurrency_box = self.generate_concurrency_box();
         let test_place_box = self.generate_test_place_box();
 
-        let mut column2;
-        if self.test_place == Some(TestPlace::Ssh) {
-            let git_address_box = self.generate_git_settings_box();
-            column2 = iced::widget::column!(concurrency_box, test_place_box, git_address_box)
-            .width(iced::Length::FillPortion(6))
-            .spacing(20);
-        } else {
-            column2 = iced::widget::column!(concurrency_box, test_place_box)
+        let mut column2 = iced::widget::column!(profiles_box, concurrency_box, test_place_box)
             .width(iced::Length::FillPortion(6))
             .spacing(20);
+        // Tests are always taken from this machine, other places get a copy.
+        if self.test_place.is_some() {
+            column2 = column2.push(self.generate_local_paths_box());
+        }
+        match self.test_place {
+            Some(TestPlace::Ssh) => {
+                column2 = column2
+                    .push(self.generate_ssh_settings_box())
+                    .push(self.generate_git_settings_box());
+            }
+            Some(TestPlace::Server) => column2 = column2.push(self.generate_server_settings_box()),
+            _ => {}
         }
 
         let column1 = iced::widget::column!().width(iced::Length::FillPortion(2));
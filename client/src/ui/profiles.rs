@@ -0,0 +1,201 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use trst_types::SshSettings;
+
+use super::{
+    preferences::{ConcurrencyMode, TestPlace},
+    program::ProgramSettings,
+};
+
+pub(super) const DEFAULT_PROFILE: &str = "default";
+
+/// Preferences saved under a profile name.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub(super) struct Profile {
+    pub concurrency: ConcurrencyMode,
+    pub threads: u32,
+    pub reserve: u32,
+    pub pin_tests: bool,
+    pub skip_smt_siblings: bool,
+    pub test_place: Option<TestPlace>,
+    pub git_address: String,
+    pub in_test_path: String,
+    pub out_test_path: String,
+    pub err_test_path: String,
+    pub compiled_program_path: String,
+    pub ssh: SshSettings,
+    /// Replaces the `ssh` command built from `ssh` when set, for example `sh -c`.
+    pub transport_command: String,
+    pub remote_dir: String,
+    pub remote_trster: String,
+    /// Address of `trster serve`, see [`trst_types::SessionAddress`].
+    pub server_address: String,
+    pub server_token: String,
+    pub program: ProgramSettings,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            concurrency: ConcurrencyMode::Disabled,
+            threads: 1,
+            reserve: 1,
+            pin_tests: true,
+            skip_smt_siblings: false,
+            test_place: None,
+            git_address: String::new(),
+            in_test_path: String::new(),
+            out_test_path: String::new(),
+            err_test_path: String::new(),
+            compiled_program_path: String::new(),
+            ssh: SshSettings::default(),
+            transport_command: String::new(),
+            remote_dir: "trst".into(),
+            remote_trster: "trst_trster".into(),
+            server_address: String::new(),
+            server_token: String::new(),
+            program: ProgramSettings::default(),
+        }
+    }
+}
+
+/// Content of the preferences file: every profile and the one in use.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(super) struct ProfileStore {
+    pub active: String,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.into(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.into(), Profile::default())]),
+        }
+    }
+}
+
+impl ProfileStore {
+    /// Preferences file in the platform configuration directory.
+    pub fn path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("trst")
+            .join("preferences.ron")
+    }
+
+    /// Reads the store, a missing file gives the default one.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("cannot read '{}': {e}", path.display())),
+        };
+
+        let mut store: Self = ron::from_str(&content)
+            .map_err(|e| format!("cannot parse '{}': {e}", path.display()))?;
+        if !store.profiles.contains_key(&store.active) {
+            let profile = store.profiles.values().next().cloned().unwrap_or_default();
+            store.profiles.insert(store.active.clone(), profile);
+        }
+
+        Ok(store)
+    }
+
+    /// Moves an unreadable preferences file aside so that saving does not
+    /// overwrite it, returns where it was moved.
+    pub fn back_up(path: &Path) -> Result<PathBuf, String> {
+        let backup = path.with_extension("ron.bak");
+        std::fs::rename(path, &backup)
+            .map(|()| backup)
+            .map_err(|e| format!("cannot move '{}' aside: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("cannot create '{}': {e}", dir.display()))?;
+        }
+        write_private(path, content.as_bytes())
+            .map_err(|e| format!("cannot write '{}': {e}", path.display()))
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.profiles[&self.active]
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+}
+
+/// Writes a file only its owner can read, the preferences hold server tokens.
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, older ones may still be readable.
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+
+    file.write_all(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trst").join("preferences.ron");
+
+        let mut store = ProfileStore::default();
+        let profile = Profile {
+            threads: 4,
+            server_token: "secret".into(),
+            ..Profile::default()
+        };
+        store.profiles.insert("fast".into(), profile);
+        store.active = "fast".into();
+        store.save(&path).unwrap();
+
+        assert_eq!(ProfileStore::load(&path).unwrap(), store);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn corrupt_file_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preferences.ron");
+        assert_eq!(ProfileStore::load(&path).unwrap(), ProfileStore::default());
+
+        std::fs::write(&path, "(active: \"default\", profiles: {").unwrap();
+        assert!(ProfileStore::load(&path).is_err());
+
+        let backup = ProfileStore::back_up(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(backup).unwrap(),
+            "(active: \"default\", profiles: {"
+        );
+        assert_eq!(ProfileStore::load(&path).unwrap(), ProfileStore::default());
+    }
+}
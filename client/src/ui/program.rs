@@ -0,0 +1,300 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use iced::{widget::text, Application};
+use serde::{Deserialize, Serialize};
+use trst_types::Language;
+
+use super::{backend::blocking, Trst, TrstMessage};
+
+type Column<'a> = iced::widget::Column<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+#[derive(Clone, PartialEq, Eq, Copy, Debug, Serialize, Deserialize)]
+pub enum ProgramKind {
+    Source,
+    Binary,
+}
+
+impl ProgramKind {
+    fn desc(&self) -> &'static str {
+        match self {
+            Self::Source => "Source file, compiled before testing",
+            Self::Binary => "Compiled binary",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum TrstProgramMessage {
+    KindSelected(ProgramKind),
+    PathChange(String),
+    LanguageSelected(Language),
+    FlagsChange(String),
+    Compile,
+    /// Binary and log of a successful compilation, or the log of a failed one.
+    Compiled(Result<(String, String), String>),
+}
+
+impl From<TrstProgramMessage> for TrstMessage {
+    fn from(program_message: TrstProgramMessage) -> Self {
+        Self::ProgramMessage(program_message)
+    }
+}
+
+/// Directory of binaries compiled from sources.
+fn build_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("trst")
+        .join("build")
+}
+
+/// Binary built from `source`, named after its full path so that sources of
+/// the same name in different directories do not share one.
+fn binary_path(source: &str) -> PathBuf {
+    let source = Path::new(source);
+    let stem = source
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("program");
+
+    let mut hasher = DefaultHasher::new();
+    std::fs::canonicalize(source)
+        .unwrap_or_else(|_| source.to_path_buf())
+        .hash(&mut hasher);
+
+    build_dir().join(format!("{stem}-{:016x}", hasher.finish()))
+}
+
+/// Compiles `source` and returns the binary with the compiler log.
+fn compile(language: Language, flags: String, source: String) -> Result<(String, String), String> {
+    let dir = build_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("cannot create '{}': {e}", dir.display()))?;
+    let binary = binary_path(&source).to_string_lossy().into_owned();
+
+    let output = std::process::Command::new(language.compiler())
+        .args(flags.split_whitespace())
+        .arg("-o")
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .map_err(|e| format!("cannot run {}: {e}", language.compiler()))?;
+
+    let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+    log.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    if output.status.success() {
+        Ok((binary, log))
+    } else {
+        log.push_str(&format!("\n{} exited with {}", language.compiler(), output.status));
+        Err(log)
+    }
+}
+
+/// Program under test, saved in the profile.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub(super) struct ProgramSettings {
+    pub kind: ProgramKind,
+    pub path: String,
+    pub language: Language,
+    pub flags: String,
+}
+
+impl Default for ProgramSettings {
+    fn default() -> Self {
+        Self {
+            kind: ProgramKind::Source,
+            path: String::new(),
+            language: Language::Cpp,
+            flags: Language::Cpp.default_flags().into(),
+        }
+    }
+}
+
+/// Program under test, either a binary or a source compiled by the client.
+#[derive(Default)]
+pub(super) struct TrstProgram {
+    settings: ProgramSettings,
+    /// Binary built from the current source, cleared when the source settings change.
+    compiled: Option<String>,
+    log: Option<String>,
+    compiling: bool,
+    /// The last compilation of the current settings failed.
+    failed: bool,
+}
+
+impl TrstProgram {
+    pub(super) fn language(&self) -> Language {
+        self.settings.language
+    }
+
+    pub(super) fn settings(&self) -> &ProgramSettings {
+        &self.settings
+    }
+
+    /// Takes over the settings of a profile, the compiled binary is kept
+    /// when they did not change.
+    pub(super) fn apply(&mut self, settings: &ProgramSettings) {
+        if self.settings != *settings {
+            self.settings = settings.clone();
+            self.compiled = None;
+            self.failed = false;
+        }
+    }
+
+    /// Settings of a chosen source, for testing places which compile it themselves.
+    pub(super) fn source(&self) -> Option<&ProgramSettings> {
+        let chosen = self.settings.kind == ProgramKind::Source && !self.settings.path.is_empty();
+        chosen.then_some(&self.settings)
+    }
+
+    /// Value of `compiled_program_path` in `RunningMode::Local`, `None` when
+    /// no program was chosen. A chosen source has one only after it was compiled.
+    pub(super) fn compiled_program_path(&self) -> Result<Option<String>, String> {
+        match self.settings.kind {
+            _ if self.settings.path.is_empty() => Ok(None),
+            ProgramKind::Binary => Ok(Some(self.settings.path.clone())),
+            ProgramKind::Source => match &self.compiled {
+                Some(binary) => Ok(Some(binary.clone())),
+                None => Err(format!("Compile '{}' before testing", self.settings.path)),
+            },
+        }
+    }
+
+    pub(super) fn update(&mut self, msg: TrstProgramMessage) -> iced::Command<TrstMessage> {
+        match msg {
+            TrstProgramMessage::KindSelected(kind) => self.settings.kind = kind,
+            TrstProgramMessage::PathChange(path) => {
+                self.settings.path = path;
+                self.compiled = None;
+                self.failed = false;
+            }
+            TrstProgramMessage::LanguageSelected(language) => {
+                if self.settings.flags == self.settings.language.default_flags() {
+                    self.settings.flags = language.default_flags().into();
+                }
+                self.settings.language = language;
+                self.compiled = None;
+                self.failed = false;
+            }
+            TrstProgramMessage::FlagsChange(flags) => {
+                self.settings.flags = flags;
+                self.compiled = None;
+                self.failed = false;
+            }
+            TrstProgramMessage::Compile => {
+                if self.compiling || self.settings.path.is_empty() {
+                    return iced::Command::none();
+                }
+                self.compiling = true;
+
+                let ProgramSettings {
+                    language,
+                    flags,
+                    path,
+                    ..
+                } = self.settings.clone();
+                return iced::Command::perform(
+                    async move {
+                        blocking(move || compile(language, flags, path))
+                            .await
+                            .unwrap_or_else(|| Err("The compilation stopped unexpectedly".into()))
+                    },
+                    |result| TrstProgramMessage::Compiled(result).into(),
+                );
+            }
+            TrstProgramMessage::Compiled(result) => {
+                self.compiling = false;
+                match result {
+                    Ok((binary, log)) => {
+                        self.compiled = Some(binary);
+                        self.failed = false;
+                        self.log = Some(log);
+                    }
+                    Err(log) => {
+                        self.compiled = None;
+                        self.failed = true;
+                        self.log = Some(log);
+                    }
+                }
+            }
+        }
+
+        iced::Command::none()
+    }
+
+    pub(super) fn view(&self) -> Column {
+        let header = text("Program").size(24);
+
+        let selected = Some(self.settings.kind);
+        let kinds = [ProgramKind::Source, ProgramKind::Binary]
+            .into_iter()
+            .fold(iced::widget::column!().spacing(5), |kinds, kind| {
+                kinds.push(iced::widget::radio(kind.desc(), kind, selected, |kind| {
+                    TrstProgramMessage::KindSelected(kind).into()
+                }))
+            });
+
+        let placeholder = match self.settings.kind {
+            ProgramKind::Source => "Path of the source file",
+            ProgramKind::Binary => "Path of the binary",
+        };
+        let path = iced::widget::TextInput::new(placeholder, &self.settings.path, |val| {
+            TrstProgramMessage::PathChange(val).into()
+        });
+
+        let mut column = iced::widget::column!(header, kinds, path).spacing(15);
+        if self.settings.kind == ProgramKind::Binary {
+            return column;
+        }
+
+        let selected = Some(self.settings.language);
+        let language = iced::widget::row!(
+            text("Language"),
+            iced::widget::pick_list(&Language::ALL[..], selected, |language| {
+                TrstProgramMessage::LanguageSelected(language).into()
+            })
+        )
+        .spacing(10);
+        let flags = iced::widget::TextInput::new("Compiler flags", &self.settings.flags, |val| {
+            TrstProgramMessage::FlagsChange(val).into()
+        });
+
+        let mut compile = iced::widget::button(text(if self.compiling {
+            "Compiling..."
+        } else {
+            "Compile"
+        }))
+        .style(iced::theme::Button::Positive);
+        if !self.compiling && !self.settings.path.is_empty() {
+            compile = compile.on_press(TrstProgramMessage::Compile.into());
+        }
+
+        let status = match &self.compiled {
+            Some(binary) => format!("Compiled to {binary}"),
+            None if self.failed => "Compilation failed".into(),
+            None => "Not compiled with the current settings".into(),
+        };
+
+        column = column.push(language).push(flags).push(compile).push(text(status));
+        if let Some(log) = &self.log {
+            column = column
+                .push(text("Last compilation log").size(18))
+                .push(iced::widget::scrollable(text(if log.is_empty() {
+                    "(empty)"
+                } else {
+                    log.as_str()
+                })));
+        }
+
+        column
+    }
+}
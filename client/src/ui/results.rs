@@ -0,0 +1,142 @@
+use iced::{widget::text, Application, Color};
+use trst_types::{FrontendMessage, RunSummary, TestMeasure, TestResult};
+
+use super::{backend::BackendConnection, Trst, TrstMessage};
+
+const FAILURE_COLOR: Color = Color::from_rgb(0.8, 0.1, 0.1);
+const SUCCESS_COLOR: Color = Color::from_rgb(0.1, 0.6, 0.1);
+const NOT_RUN_COLOR: Color = Color::from_rgb(0.6, 0.5, 0.1);
+
+type Column<'a> = iced::widget::Column<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+type Row<'a> = iced::widget::Row<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+/// Verdicts of a run, either the current one or one opened from the history.
+#[derive(Default)]
+pub(super) struct TrstResults {
+    title: String,
+    results: Vec<TestResult>,
+    /// Why the run could not be started or finished.
+    error: Option<String>,
+    /// Outcome of the last action on the shown results.
+    notice: Option<String>,
+}
+
+impl TrstResults {
+    pub(super) fn show(&mut self, title: String, results: Vec<TestResult>) {
+        self.title = title;
+        self.results = results;
+        self.error = None;
+        self.notice = None;
+    }
+
+    /// Starts collecting results of a new run.
+    pub(super) fn clear(&mut self, title: String) {
+        self.show(title, Vec::new());
+    }
+
+    pub(super) fn push(&mut self, result: TestResult) {
+        self.results.push(result);
+    }
+
+    pub(super) fn fail(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub(super) fn notify(&mut self, notice: String) {
+        self.notice = Some(notice);
+    }
+
+    pub(super) fn results(&self) -> &[TestResult] {
+        &self.results
+    }
+
+    /// The run is controlled while `backend` runs, its results can be saved
+    /// as the baseline once it is over.
+    pub(super) fn view(&self, backend: Option<&BackendConnection>) -> Column {
+        let summary = RunSummary::new(&self.results, Vec::new());
+        let header = text(format!(
+            "{}: {} passed, {} failed, {} not run",
+            self.title,
+            summary.passed(),
+            summary.failed(),
+            summary.not_run()
+        ))
+        .size(24);
+
+        let rows = self
+            .results
+            .iter()
+            .fold(iced::widget::column!().spacing(5), |rows, result| {
+                rows.push(Self::result_row(result))
+            });
+
+        let mut save_baseline =
+            iced::widget::button(text("Save as baseline")).style(iced::theme::Button::Secondary);
+        if backend.is_none() && !self.results.is_empty() {
+            save_baseline = save_baseline.on_press(TrstMessage::SaveBaseline);
+        }
+
+        let mut buttons = iced::widget::row!().spacing(10);
+        if let Some(backend) = backend {
+            let (label, message) = if backend.is_paused() {
+                ("Resume", FrontendMessage::Resume)
+            } else {
+                ("Pause", FrontendMessage::Pause)
+            };
+            buttons = buttons
+                .push(iced::widget::button(text(label)).on_press(TrstMessage::ControlRun(message)))
+                .push(
+                    iced::widget::button(text("Cancel"))
+                        .on_press(TrstMessage::ControlRun(FrontendMessage::Cancel))
+                        .style(iced::theme::Button::Destructive),
+                );
+        }
+        buttons = buttons.push(save_baseline);
+
+        let mut column = iced::widget::column!(header, buttons).spacing(15);
+        if let Some(error) = &self.error {
+            column = column.push(text(error).style(iced::theme::Text::Color(FAILURE_COLOR)));
+        }
+        if let Some(notice) = &self.notice {
+            column = column.push(text(notice));
+        }
+
+        column.push(iced::widget::scrollable(rows))
+    }
+
+    fn result_row(result: &TestResult) -> Row {
+        let color = match result.outcome() {
+            TestMeasure::Success { .. } => SUCCESS_COLOR,
+            outcome if outcome.is_failure() => FAILURE_COLOR,
+            _ => NOT_RUN_COLOR,
+        };
+
+        let mut verdict = result.outcome().verdict().to_string();
+        if result.is_flaky() {
+            verdict += " (flaky)";
+        }
+
+        let time = result
+            .time()
+            .map(|time| format!("{time:.3}s"))
+            .unwrap_or_else(|| "-".into());
+
+        iced::widget::row!(
+            text(result.name()).width(iced::Length::FillPortion(4)),
+            text(verdict)
+                .style(iced::theme::Text::Color(color))
+                .width(iced::Length::FillPortion(3)),
+            text(time).width(iced::Length::FillPortion(2))
+        )
+        .spacing(10)
+    }
+}
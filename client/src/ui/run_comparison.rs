@@ -0,0 +1,110 @@
+use iced::{widget::text, Application, Color};
+use trst_types::{format_timestamp, RunComparison, RunRecord, TestDelta, VerdictChange};
+
+use super::Trst;
+
+const FAILING_COLOR: Color = Color::from_rgb(0.8, 0.1, 0.1);
+const PASSING_COLOR: Color = Color::from_rgb(0.1, 0.6, 0.1);
+const CHANGED_COLOR: Color = Color::from_rgb(0.6, 0.5, 0.1);
+
+type Column<'a> = iced::widget::Column<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+type Row<'a> = iced::widget::Row<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+/// Side by side comparison of two runs from the history.
+#[derive(Default)]
+pub(super) struct TrstRunComparison {
+    title: String,
+    comparison: Option<RunComparison>,
+}
+
+impl TrstRunComparison {
+    pub(super) fn set(&mut self, before: &RunRecord, after: &RunRecord) {
+        self.title = format!(
+            "{} vs {}",
+            format_timestamp(before.timestamp),
+            format_timestamp(after.timestamp)
+        );
+        self.comparison = Some(RunComparison::new(before, after));
+    }
+
+    pub(super) fn view(&self) -> Column {
+        let Some(comparison) = &self.comparison else {
+            return iced::widget::column!(text("Select two runs in Last runs to compare them."));
+        };
+
+        let program = if comparison.program_changed {
+            "Program changed"
+        } else {
+            "Program unchanged"
+        };
+        let config = if comparison.config_changes.is_empty() {
+            "Configuration unchanged".to_string()
+        } else {
+            format!("Configuration changed: {}", comparison.config_changes.join(", "))
+        };
+
+        let counts = format!(
+            "{} newly failing, {} newly passing",
+            comparison.with_change(VerdictChange::NewlyFailing).count(),
+            comparison.with_change(VerdictChange::NewlyPassing).count()
+        );
+
+        let rows = comparison
+            .tests
+            .iter()
+            .fold(iced::widget::column!().spacing(5), |rows, test| {
+                rows.push(Self::test_row(test))
+            });
+
+        iced::widget::column!(
+            text(&self.title).size(24),
+            text(program),
+            text(config),
+            text(counts),
+            iced::widget::scrollable(rows)
+        )
+        .spacing(10)
+    }
+
+    fn test_row(test: &TestDelta) -> Row {
+        let color = match test.change {
+            VerdictChange::NewlyFailing | VerdictChange::Removed => FAILING_COLOR,
+            VerdictChange::NewlyPassing | VerdictChange::Added => PASSING_COLOR,
+            VerdictChange::Changed => CHANGED_COLOR,
+            VerdictChange::Unchanged => Color::BLACK,
+        };
+
+        let verdicts = format!(
+            "{} -> {}",
+            test.before.as_ref().map_or("-", |outcome| outcome.verdict()),
+            test.after.as_ref().map_or("-", |outcome| outcome.verdict())
+        );
+        let time = test
+            .time_delta
+            .map(|delta| format!("{delta:+.3}s"))
+            .unwrap_or_else(|| "-".into());
+        let memory = test
+            .memory_delta
+            .map(|delta| format!("{:+} KiB", delta / 1024))
+            .unwrap_or_else(|| "-".into());
+
+        iced::widget::row!(
+            text(&test.name).width(iced::Length::FillPortion(3)),
+            text(verdicts)
+                .style(iced::theme::Text::Color(color))
+                .width(iced::Length::FillPortion(4)),
+            text(time).width(iced::Length::FillPortion(2)),
+            text(memory).width(iced::Length::FillPortion(2))
+        )
+        .spacing(10)
+    }
+}
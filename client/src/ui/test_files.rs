@@ -0,0 +1,257 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// Extensions of the files which make up a test case.
+pub(super) const EXTENSIONS: [&str; 3] = ["in", "out", "err"];
+
+/// Test cases stored in a directory as `<name>.in`, `<name>.out` and `<name>.err` files.
+pub(super) struct TestFiles<'a> {
+    directory: &'a Path,
+}
+
+impl<'a> TestFiles<'a> {
+    pub fn new(directory: &'a Path) -> Self {
+        Self { directory }
+    }
+
+    pub fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.directory.join(format!("{name}.{extension}"))
+    }
+
+    /// Names of the test cases with at least one of their files present.
+    pub fn names(&self) -> Result<BTreeSet<String>, String> {
+        let entries = std::fs::read_dir(self.directory)
+            .map_err(|e| format!("Cannot read '{}': {e}", self.directory.display()))?;
+
+        Ok(entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| EXTENSIONS.contains(&extension))
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect())
+    }
+
+    /// Fails unless `name` is usable for a new test and none of its files exist.
+    pub fn check_new_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(std::path::is_separator)
+        {
+            return Err(format!("'{name}' is not a valid test name"));
+        }
+        if EXTENSIONS
+            .iter()
+            .any(|extension| self.path(name, extension).exists())
+        {
+            return Err(format!("Test '{name}' already exists"));
+        }
+
+        Ok(())
+    }
+
+    /// Creates an empty input and expected output under `name`.
+    pub fn create(&self, name: &str) -> Result<(), String> {
+        self.check_new_name(name)?;
+
+        let mut created = Vec::new();
+        for extension in ["in", "out"] {
+            let path = self.path(name, extension);
+            let written = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path);
+            if let Err(e) = written {
+                remove_all(&created);
+                return Err(format!("Cannot create '{}': {e}", path.display()));
+            }
+            created.push(path);
+        }
+
+        Ok(())
+    }
+
+    /// Renames every file of the test, either all of them or none are renamed.
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<(), String> {
+        self.check_new_name(new_name)?;
+
+        all_or_nothing(
+            &self.existing_files(name, new_name),
+            |from, to| std::fs::rename(from, to),
+            |from, to| std::fs::rename(to, from),
+        )
+    }
+
+    /// Copies every file of the test, copies are removed again if one fails.
+    pub fn duplicate(&self, name: &str, new_name: &str) -> Result<(), String> {
+        self.check_new_name(new_name)?;
+
+        all_or_nothing(
+            &self.existing_files(name, new_name),
+            |from, to| {
+                std::fs::copy(from, to).map(|_| ()).inspect_err(|_| {
+                    // A failed copy may leave a partial file behind.
+                    let _ = std::fs::remove_file(to);
+                })
+            },
+            |_, to| std::fs::remove_file(to),
+        )
+    }
+
+    /// Removes every file of the test.
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        for extension in EXTENSIONS {
+            let path = self.path(name, extension);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .map_err(|e| format!("Cannot delete '{}': {e}", path.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the expected output of the test, one line per entry.
+    pub fn save_output(&self, name: &str, lines: &[String]) -> Result<(), String> {
+        let mut output = lines.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+
+        let path = self.path(name, "out");
+        std::fs::write(&path, output).map_err(|e| format!("Cannot write '{}': {e}", path.display()))
+    }
+
+    /// Files of test `name` which exist, paired with their paths under `new_name`.
+    fn existing_files(&self, name: &str, new_name: &str) -> Vec<(PathBuf, PathBuf)> {
+        EXTENSIONS
+            .iter()
+            .map(|extension| (self.path(name, extension), self.path(new_name, extension)))
+            .filter(|(from, _)| from.exists())
+            .collect()
+    }
+}
+
+/// Applies `operation` to every pair of paths, when one fails `undo` is applied
+/// to the pairs done before it.
+fn all_or_nothing(
+    files: &[(PathBuf, PathBuf)],
+    operation: impl Fn(&Path, &Path) -> std::io::Result<()>,
+    undo: impl Fn(&Path, &Path) -> std::io::Result<()>,
+) -> Result<(), String> {
+    for (done, (from, to)) in files.iter().enumerate() {
+        if let Err(e) = operation(from, to) {
+            for (from, to) in files[..done].iter().rev() {
+                if let Err(e) = undo(from, to) {
+                    log::warn!("Cannot restore '{}'. Error = {e}", from.display());
+                }
+            }
+            return Err(format!("Cannot update '{}': {e}", from.display()));
+        }
+    }
+
+    Ok(())
+}
+
+fn remove_all(paths: &[PathBuf]) {
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(path) {
+            log::warn!("Cannot remove '{}'. Error = {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files_of(dir: &Path) -> Vec<String> {
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn create_rename_duplicate_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = TestFiles::new(dir.path());
+
+        files.create("a").unwrap();
+        files.save_output("a", &["1".into(), "2".into()]).unwrap();
+        std::fs::write(files.path("a", "err"), "oops").unwrap();
+        assert_eq!(files_of(dir.path()), ["a.err", "a.in", "a.out"]);
+        assert_eq!(
+            std::fs::read_to_string(files.path("a", "out")).unwrap(),
+            "1\n2\n"
+        );
+
+        files.rename("a", "b").unwrap();
+        assert_eq!(files_of(dir.path()), ["b.err", "b.in", "b.out"]);
+
+        files.duplicate("b", "c").unwrap();
+        assert_eq!(
+            files.names().unwrap().into_iter().collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+        assert_eq!(
+            std::fs::read_to_string(files.path("c", "err")).unwrap(),
+            "oops"
+        );
+
+        files.delete("b").unwrap();
+        assert_eq!(files_of(dir.path()), ["c.err", "c.in", "c.out"]);
+    }
+
+    #[test]
+    fn rejects_unusable_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = TestFiles::new(dir.path());
+        std::fs::write(files.path("taken", "err"), "").unwrap();
+
+        for name in ["", ".", "..", "a/b", "taken"] {
+            assert!(files.check_new_name(name).is_err(), "{name:?}");
+        }
+        assert!(files.create("taken").is_err());
+        assert_eq!(files_of(dir.path()), ["taken.err"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_duplicate_leaves_no_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = TestFiles::new(dir.path());
+        files.create("a").unwrap();
+        // A directory cannot be copied, so the second file fails.
+        std::fs::remove_file(files.path("a", "out")).unwrap();
+        std::fs::create_dir(files.path("a", "out")).unwrap();
+
+        assert!(files.duplicate("a", "b").is_err());
+        assert_eq!(files_of(dir.path()), ["a.in", "a.out"]);
+    }
+
+    #[test]
+    fn failed_rename_is_rolled_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = TestFiles::new(dir.path());
+        files.create("a").unwrap();
+        std::fs::write(files.path("a", "err"), "").unwrap();
+
+        let renamed = all_or_nothing(
+            &files.existing_files("a", "b"),
+            |from, to| match from.extension().unwrap().to_str() {
+                Some("err") => Err(std::io::ErrorKind::PermissionDenied.into()),
+                _ => std::fs::rename(from, to),
+            },
+            |from, to| std::fs::rename(to, from),
+        );
+
+        assert!(renamed.is_err());
+        assert_eq!(files_of(dir.path()), ["a.err", "a.in", "a.out"]);
+    }
+}
@@ -0,0 +1,576 @@
+use std::{collections::BTreeSet, path::Path};
+
+use iced::{widget::text, Application};
+
+use super::{
+    backend::{blocking, LOCAL_TRSTER},
+    test_files::TestFiles,
+    Trst, TrstMessage,
+};
+
+/// Lines of the input shown in the view, the rest is cut off.
+const INPUT_PREVIEW_LINES: usize = 200;
+
+type Column<'a> = iced::widget::Column<
+    'a,
+    <Trst as Application>::Message,
+    iced::Renderer<<Trst as Application>::Theme>,
+>;
+
+#[derive(Clone, Debug)]
+pub enum TrstTestsMessage {
+    DirectoryChange(String),
+    Rescan,
+    Select(String),
+    IncludeToggle(String, bool),
+    IncludeAll,
+    IncludeNone,
+    NewNameChange(String),
+    Create,
+    Rename,
+    Duplicate,
+    Delete,
+    OutputLineChange(usize, String),
+    /// Inserts an empty line after the given one.
+    InsertOutputLine(usize),
+    RemoveOutputLine(usize),
+    SaveOutput,
+    ReferenceChange(String),
+    PickReference,
+    /// Runs the reference and shows how the expected files would change.
+    PreviewExpected,
+    WriteExpected,
+    /// Report of trster after previewing or writing, or its error.
+    ExpectedGenerated(bool, Result<String, String>),
+}
+
+impl From<TrstTestsMessage> for TrstMessage {
+    fn from(tests_message: TrstTestsMessage) -> Self {
+        Self::TestsMessage(tests_message)
+    }
+}
+
+/// Files of the selected test case.
+struct TestCase {
+    name: String,
+    input: Option<String>,
+    output: Vec<String>,
+    error: Option<String>,
+    /// Expected output was edited and not written yet.
+    edited: bool,
+}
+
+/// Test cases found in a directory as `<name>.in`, `<name>.out` and `<name>.err` files.
+#[derive(Default)]
+pub(super) struct TrstTests {
+    directory: String,
+    tests: Vec<String>,
+    /// Tests which go into `Config::test_list`.
+    included: BTreeSet<String>,
+    selected: Option<TestCase>,
+    new_name: String,
+    error: Option<String>,
+    /// Program whose outputs become the expected ones.
+    reference: String,
+    /// Directories of the preferences the expected outputs are generated in,
+    /// the test directory is used when they are empty.
+    out_directory: String,
+    err_directory: String,
+    /// Diff of the expected files shown before they are written.
+    expected_preview: Option<String>,
+    /// Outcome of the last write of the expected files.
+    expected_status: Option<String>,
+    generating: bool,
+}
+
+impl TrstTests {
+    /// Tests chosen for the run, in the order they were discovered.
+    pub(super) fn test_list(&self) -> Vec<String> {
+        self.tests
+            .iter()
+            .filter(|name| self.included.contains(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// Uses `directory` unless one was chosen on this screen already.
+    pub(super) fn set_default_directory(&mut self, directory: &str) {
+        if self.directory.is_empty() {
+            self.directory = directory.into();
+        }
+    }
+
+    pub(super) fn set_expected_directories(&mut self, out_directory: &str, err_directory: &str) {
+        self.out_directory = out_directory.into();
+        self.err_directory = err_directory.into();
+    }
+
+    fn files(&self) -> TestFiles<'_> {
+        TestFiles::new(Path::new(&self.directory))
+    }
+
+    /// Lists the test cases again, newly found tests are included.
+    pub(super) fn rescan(&mut self) {
+        if self.directory.is_empty() {
+            return;
+        }
+
+        let names = match self.files().names() {
+            Ok(names) => names,
+            Err(e) => {
+                self.tests.clear();
+                self.error = Some(e);
+                return;
+            }
+        };
+
+        for name in names.difference(&self.tests.iter().cloned().collect()) {
+            self.included.insert(name.clone());
+        }
+        self.included.retain(|name| names.contains(name));
+        self.tests = names.into_iter().collect();
+        self.error = None;
+
+        let selected = self.selected.take().map(|test| test.name);
+        if let Some(name) = selected.filter(|name| self.tests.contains(name)) {
+            self.select(name);
+        }
+    }
+
+    fn select(&mut self, name: String) {
+        let files = self.files();
+        let read = |extension| std::fs::read_to_string(files.path(&name, extension)).ok();
+
+        self.selected = Some(TestCase {
+            input: read("in"),
+            output: read("out")
+                .map(|output| output.lines().map(String::from).collect())
+                .unwrap_or_default(),
+            error: read("err"),
+            edited: false,
+            name,
+        });
+    }
+
+    /// Name typed for a new test, if it is usable and not taken.
+    fn new_name(&mut self) -> Option<String> {
+        let name = self.new_name.trim().to_string();
+
+        match self.files().check_new_name(&name) {
+            Ok(()) => Some(name),
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Name of the selected test.
+    fn selected_name(&self) -> Option<String> {
+        self.selected.as_ref().map(|test| test.name.clone())
+    }
+
+    pub(super) fn update(&mut self, msg: TrstTestsMessage) -> iced::Command<TrstMessage> {
+        let result = match msg {
+            TrstTestsMessage::DirectoryChange(directory) => {
+                self.directory = directory;
+                self.expected_preview = None;
+                Ok(())
+            }
+            TrstTestsMessage::Rescan => {
+                self.rescan();
+                return iced::Command::none();
+            }
+            TrstTestsMessage::Select(name) => {
+                self.select(name);
+                Ok(())
+            }
+            TrstTestsMessage::IncludeToggle(name, included) => {
+                if included {
+                    self.included.insert(name);
+                } else {
+                    self.included.remove(&name);
+                }
+                Ok(())
+            }
+            TrstTestsMessage::IncludeAll => {
+                self.included = self.tests.iter().cloned().collect();
+                Ok(())
+            }
+            TrstTestsMessage::IncludeNone => {
+                self.included.clear();
+                Ok(())
+            }
+            TrstTestsMessage::NewNameChange(name) => {
+                self.new_name = name;
+                Ok(())
+            }
+            TrstTestsMessage::Create => {
+                let Some(name) = self.new_name() else {
+                    return iced::Command::none();
+                };
+
+                self.files().create(&name).map(|()| self.created(name))
+            }
+            TrstTestsMessage::Rename => {
+                let (Some(old_name), Some(name)) = (self.selected_name(), self.new_name()) else {
+                    return iced::Command::none();
+                };
+
+                self.files().rename(&old_name, &name).map(|()| {
+                    self.included.remove(&old_name);
+                    self.selected = None;
+                    self.created(name);
+                })
+            }
+            TrstTestsMessage::Duplicate => {
+                let (Some(old_name), Some(name)) = (self.selected_name(), self.new_name()) else {
+                    return iced::Command::none();
+                };
+
+                self.files()
+                    .duplicate(&old_name, &name)
+                    .map(|()| self.created(name))
+            }
+            TrstTestsMessage::Delete => {
+                let Some(name) = self.selected_name() else {
+                    return iced::Command::none();
+                };
+
+                let deleted = self.files().delete(&name);
+                if deleted.is_ok() {
+                    self.selected = None;
+                }
+                self.rescan();
+                deleted
+            }
+            TrstTestsMessage::OutputLineChange(index, line) => {
+                if let Some(test) = &mut self.selected {
+                    if let Some(old) = test.output.get_mut(index) {
+                        *old = line;
+                        test.edited = true;
+                    }
+                }
+                Ok(())
+            }
+            TrstTestsMessage::InsertOutputLine(index) => {
+                if let Some(test) = &mut self.selected {
+                    test.output.insert((index + 1).min(test.output.len()), String::new());
+                    test.edited = true;
+                }
+                Ok(())
+            }
+            TrstTestsMessage::RemoveOutputLine(index) => {
+                if let Some(test) = &mut self.selected {
+                    if index < test.output.len() {
+                        test.output.remove(index);
+                        test.edited = true;
+                    }
+                }
+                Ok(())
+            }
+            TrstTestsMessage::SaveOutput => self.save_output(),
+            TrstTestsMessage::ReferenceChange(reference) => {
+                self.reference = reference;
+                self.expected_preview = None;
+                Ok(())
+            }
+            TrstTestsMessage::PickReference => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_title("Reference program")
+                    .pick_file()
+                {
+                    self.reference = path.to_string_lossy().into_owned();
+                    self.expected_preview = None;
+                }
+                Ok(())
+            }
+            TrstTestsMessage::PreviewExpected => return self.generate_expected(false),
+            TrstTestsMessage::WriteExpected => return self.generate_expected(true),
+            TrstTestsMessage::ExpectedGenerated(written, report) => {
+                self.generating = false;
+                match report {
+                    Ok(report) if written => {
+                        self.expected_preview = None;
+                        self.expected_status = report.lines().last().map(String::from);
+                        self.rescan();
+                        Ok(())
+                    }
+                    Ok(report) => {
+                        self.expected_preview = Some(report);
+                        self.expected_status = None;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+
+        iced::Command::none()
+    }
+
+    /// Runs `trster generate-outputs` on the directory in the background,
+    /// only showing the changes unless `write` is set.
+    fn generate_expected(&mut self, write: bool) -> iced::Command<TrstMessage> {
+        if self.generating || self.reference.is_empty() || self.directory.is_empty() {
+            return iced::Command::none();
+        }
+        self.generating = true;
+
+        let (reference, directory) = (self.reference.clone(), self.directory.clone());
+        let (out_directory, err_directory) =
+            (self.out_directory.clone(), self.err_directory.clone());
+        iced::Command::perform(
+            async move {
+                blocking(move || -> Result<String, String> {
+                    let mut command = std::process::Command::new(LOCAL_TRSTER);
+                    command.args([
+                        "generate-outputs",
+                        "--reference",
+                        &reference,
+                        "--in-dir",
+                        &directory,
+                    ]);
+                    if !out_directory.is_empty() {
+                        command.args(["--out-dir", &out_directory]);
+                    }
+                    if !err_directory.is_empty() {
+                        command.args(["--err-dir", &err_directory]);
+                    }
+                    if write {
+                        command.arg("--write");
+                    }
+
+                    let output = command
+                        .output()
+                        .map_err(|e| format!("Cannot start {LOCAL_TRSTER}: {e}"))?;
+                    if output.status.success() {
+                        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+                    } else {
+                        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                    }
+                })
+                .await
+                .unwrap_or_else(|| Err(format!("{LOCAL_TRSTER} stopped unexpectedly")))
+            },
+            move |report| TrstTestsMessage::ExpectedGenerated(write, report).into(),
+        )
+    }
+
+    /// Shows a test which was just written under `name`.
+    fn created(&mut self, name: String) {
+        self.new_name.clear();
+        self.rescan();
+        self.select(name);
+    }
+
+    fn save_output(&mut self) -> Result<(), String> {
+        let Some(test) = &self.selected else {
+            return Ok(());
+        };
+
+        self.files().save_output(&test.name, &test.output)?;
+        if let Some(test) = &mut self.selected {
+            test.edited = false;
+        }
+        self.error = None;
+
+        Ok(())
+    }
+
+    pub(super) fn view(&self) -> Column {
+        let header = text("Tests").size(24);
+
+        let directory = iced::widget::row!(
+            iced::widget::TextInput::new("Tests directory", &self.directory, |val| {
+                TrstTestsMessage::DirectoryChange(val).into()
+            })
+            .on_submit(TrstTestsMessage::Rescan.into()),
+            iced::widget::button(text("Scan")).on_press(TrstTestsMessage::Rescan.into())
+        )
+        .spacing(10);
+
+        let mut column = iced::widget::column!(header, directory, self.expected_view()).spacing(15);
+        if let Some(error) = &self.error {
+            column = column.push(text(error));
+        }
+
+        let content = iced::widget::row!(
+            self.list_view().width(iced::Length::FillPortion(2)),
+            self.test_view().width(iced::Length::FillPortion(5))
+        )
+        .spacing(20);
+
+        column.push(content)
+    }
+
+    fn expected_view(&self) -> Column {
+        let mut preview = iced::widget::button(text("Preview expected outputs"));
+        let mut write = iced::widget::button(text("Write expected outputs"));
+        if !self.generating && !self.reference.is_empty() && !self.directory.is_empty() {
+            preview = preview.on_press(TrstTestsMessage::PreviewExpected.into());
+            // Expected files are only written after their changes were shown.
+            if self.expected_preview.is_some() {
+                write = write.on_press(TrstTestsMessage::WriteExpected.into());
+            }
+        }
+
+        let reference = iced::widget::row!(
+            iced::widget::TextInput::new("Reference program", &self.reference, |val| {
+                TrstTestsMessage::ReferenceChange(val).into()
+            }),
+            iced::widget::button(text("Browse"))
+                .on_press(TrstTestsMessage::PickReference.into())
+                .style(iced::theme::Button::Secondary),
+            preview,
+            write
+        )
+        .spacing(10);
+
+        let mut column = iced::widget::column!(reference).spacing(10);
+        if self.generating {
+            column = column.push(text("Running the reference..."));
+        }
+        if let Some(status) = &self.expected_status {
+            column = column.push(text(status));
+        }
+        if let Some(report) = &self.expected_preview {
+            column = column.push(iced::widget::scrollable(text(report)).height(200));
+        }
+
+        column
+    }
+
+    fn list_view(&self) -> Column {
+        let counts = text(format!(
+            "{} of {} tests included",
+            self.included.len(),
+            self.tests.len()
+        ));
+        let buttons = iced::widget::row!(
+            iced::widget::button(text("All"))
+                .on_press(TrstTestsMessage::IncludeAll.into())
+                .style(iced::theme::Button::Secondary),
+            iced::widget::button(text("None"))
+                .on_press(TrstTestsMessage::IncludeNone.into())
+                .style(iced::theme::Button::Secondary)
+        )
+        .spacing(10);
+
+        let rows = self
+            .tests
+            .iter()
+            .fold(iced::widget::column!().spacing(5), |rows, name| {
+                let included = self.included.contains(name);
+                let toggle_name = name.clone();
+
+                rows.push(
+                    iced::widget::row!(
+                        iced::widget::checkbox(name, included, move |val| {
+                            TrstTestsMessage::IncludeToggle(toggle_name.clone(), val).into()
+                        })
+                        .width(iced::Length::Fill),
+                        iced::widget::button(text("Show"))
+                            .on_press(TrstTestsMessage::Select(name.clone()).into())
+                    )
+                    .spacing(10),
+                )
+            });
+
+        iced::widget::column!(counts, buttons, iced::widget::scrollable(rows)).spacing(10)
+    }
+
+    fn test_view(&self) -> Column {
+        let name_input = iced::widget::TextInput::new("Test name", &self.new_name, |val| {
+            TrstTestsMessage::NewNameChange(val).into()
+        });
+
+        let mut create = iced::widget::button(text("Create"));
+        let mut rename = iced::widget::button(text("Rename"));
+        let mut duplicate = iced::widget::button(text("Duplicate"));
+        let mut delete =
+            iced::widget::button(text("Delete")).style(iced::theme::Button::Destructive);
+        if !self.new_name.trim().is_empty() {
+            create = create.on_press(TrstTestsMessage::Create.into());
+        }
+        if self.selected.is_some() {
+            delete = delete.on_press(TrstTestsMessage::Delete.into());
+            if !self.new_name.trim().is_empty() {
+                rename = rename.on_press(TrstTestsMessage::Rename.into());
+                duplicate = duplicate.on_press(TrstTestsMessage::Duplicate.into());
+            }
+        }
+
+        let actions = iced::widget::column!(
+            name_input,
+            iced::widget::row!(create, rename, duplicate, delete).spacing(10)
+        )
+        .spacing(10);
+
+        let Some(test) = &self.selected else {
+            return iced::widget::column!(actions, text("Select a test to see its files."))
+                .spacing(15);
+        };
+
+        let input = match &test.input {
+            Some(input) => {
+                let mut lines: Vec<_> = input.lines().take(INPUT_PREVIEW_LINES + 1).collect();
+                if lines.len() > INPUT_PREVIEW_LINES {
+                    lines[INPUT_PREVIEW_LINES] = "...";
+                }
+                lines.join("\n")
+            }
+            None => "No input file".into(),
+        };
+
+        let output = test.output.iter().enumerate().fold(
+            iced::widget::column!().spacing(5),
+            |lines, (index, line)| {
+                lines.push(
+                    iced::widget::row!(
+                        iced::widget::TextInput::new("", line, move |val| {
+                            TrstTestsMessage::OutputLineChange(index, val).into()
+                        })
+                        .on_submit(TrstTestsMessage::InsertOutputLine(index).into()),
+                        iced::widget::button(text("-"))
+                            .on_press(TrstTestsMessage::RemoveOutputLine(index).into())
+                            .style(iced::theme::Button::Secondary)
+                    )
+                    .spacing(5),
+                )
+            },
+        );
+
+        let mut save = iced::widget::button(text("Save expected output"));
+        if test.edited {
+            save = save.on_press(TrstTestsMessage::SaveOutput.into());
+        }
+        let output_buttons = iced::widget::row!(
+            iced::widget::button(text("Add line"))
+                .on_press(TrstTestsMessage::InsertOutputLine(test.output.len()).into())
+                .style(iced::theme::Button::Secondary),
+            save
+        )
+        .spacing(10);
+
+        let mut files = iced::widget::column!(
+            text(&test.name).size(20),
+            text("Input").size(18),
+            text(input),
+            text("Expected output").size(18),
+            output,
+            output_buttons
+        )
+        .spacing(10);
+        if let Some(error) = &test.error {
+            files = files.push(text("Expected error output").size(18));
+            files = files.push(text(error));
+        }
+
+        iced::widget::column!(actions, iced::widget::scrollable(files)).spacing(15)
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fmt::Write, path::PathBuf, process::ExitCode};
+
+use trst_types::{
+    format_timestamp, RunComparison, RunHistory, RunRecord, TestMeasure, VerdictChange,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct CompareArgs {
+    /// Directory of the run history
+    #[arg(long)]
+    history_dir: PathBuf,
+
+    /// Earlier run, counted from the newest one which is 1
+    #[arg(default_value_t = 2)]
+    before: usize,
+
+    /// Later run, counted from the newest one which is 1
+    #[arg(default_value_t = 1)]
+    after: usize,
+
+    /// Print the comparison as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: &CompareArgs) -> ExitCode {
+    let runs = match RunHistory::open(&args.history_dir).and_then(|history| history.load()) {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!(
+                "error: cannot read run history in '{}': {e}",
+                args.history_dir.display()
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let select = |position: usize| {
+        position
+            .checked_sub(1)
+            .and_then(|back| runs.len().checked_sub(back + 1))
+            .map(|index| &runs[index])
+    };
+    let (Some(before), Some(after)) = (select(args.before), select(args.after)) else {
+        eprintln!(
+            "error: history holds {} runs, cannot compare runs {} and {}",
+            runs.len(),
+            args.before,
+            args.after
+        );
+        return ExitCode::FAILURE;
+    };
+
+    let comparison = RunComparison::new(before, after);
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&comparison).expect("comparison is serializable")
+        );
+    } else {
+        print!("{}", render(before, after, &comparison));
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn render(before: &RunRecord, after: &RunRecord, comparison: &RunComparison) -> String {
+    let mut text = format!(
+        "Comparing run of {} with run of {}\n",
+        format_timestamp(before.timestamp),
+        format_timestamp(after.timestamp)
+    );
+
+    let hash = |run: &RunRecord| {
+        run.program_hash
+            .as_deref()
+            .map(|hash| hash[..hash.len().min(12)].to_string())
+            .unwrap_or_else(|| "unknown".into())
+    };
+    if comparison.program_changed {
+        let _ = writeln!(
+            text,
+            "Program: changed, {} -> {}",
+            hash(before),
+            hash(after)
+        );
+    } else {
+        let _ = writeln!(text, "Program: unchanged");
+    }
+    if comparison.config_changes.is_empty() {
+        let _ = writeln!(text, "Configuration: unchanged");
+    } else {
+        let _ = writeln!(
+            text,
+            "Configuration: changed {}",
+            comparison.config_changes.join(", ")
+        );
+    }
+
+    let sections = [
+        (VerdictChange::NewlyFailing, "Newly failing"),
+        (VerdictChange::NewlyPassing, "Newly passing"),
+        (VerdictChange::Changed, "Changed verdict"),
+        (VerdictChange::Added, "Added"),
+        (VerdictChange::Removed, "Removed"),
+    ];
+    let verdict = |outcome: &Option<TestMeasure>| {
+        outcome
+            .as_ref()
+            .map(|outcome| outcome.verdict())
+            .unwrap_or("-")
+    };
+    for (change, title) in sections {
+        let tests: Vec<_> = comparison.with_change(change).collect();
+        if tests.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(text, "\n{title} ({}):", tests.len());
+        for test in tests {
+            let _ = writeln!(
+                text,
+                "  {:<24} {} -> {}",
+                test.name,
+                verdict(&test.before),
+                verdict(&test.after)
+            );
+        }
+    }
+
+    let measured: Vec<_> = comparison
+        .tests
+        .iter()
+        .filter(|test| test.time_delta.is_some() || test.memory_delta.is_some())
+        .collect();
+    if !measured.is_empty() {
+        let _ = writeln!(text, "\n  {:<24} {:>12} {:>14}", "Test", "Time", "Memory");
+        for test in measured {
+            let time = test
+                .time_delta
+                .map(|delta| format!("{delta:+.3}s"))
+                .unwrap_or_else(|| "-".into());
+            let memory = test
+                .memory_delta
+                .map(|delta| format!("{:+} KiB", delta / 1024))
+                .unwrap_or_else(|| "-".into());
+            let _ = writeln!(text, "  {:<24} {time:>12} {memory:>14}", test.name);
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use trst_types::TestResult;
+
+    use super::*;
+    use crate::test_support::{local_config, passed};
+
+    fn run(timestamp: u64, results: Vec<TestResult>) -> RunRecord {
+        RunRecord {
+            timestamp,
+            config: local_config("t", "p", vec!["a".into(), "b".into()]),
+            program_hash: Some(format!("hash{timestamp}")),
+            results,
+        }
+    }
+
+    #[test]
+    fn rendering() {
+        let before = run(0, vec![passed("a", 1.0), passed("b", 1.0)]);
+        let after = run(
+            60,
+            vec![
+                passed("a", 0.5),
+                TestResult::new("b".into(), TestMeasure::Timeout),
+            ],
+        );
+
+        let text = render(&before, &after, &RunComparison::new(&before, &after));
+
+        assert_eq!(
+            text,
+            "Comparing run of 1970-01-01 00:00:00 UTC with run of 1970-01-01 00:01:00 UTC\n\
+             Program: changed, hash0 -> hash60\n\
+             Configuration: unchanged\n\
+             \n\
+             Newly failing (1):\n  \
+             b                        passed -> time limit exceeded\n\
+             \n  \
+             Test                             Time         Memory\n  \
+             a                             -0.500s              -\n"
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use std::{collections::HashSet, fmt::Display, path::Path, time::Duration};
+
+use trst_types::{Config, ConfigIssue, RunningMode};
+
+use crate::testing::TestFilter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Ron,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    const ALL: [ConfigFormat; 3] = [Self::Ron, Self::Toml, Self::Json];
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "ron" => Some(Self::Ron),
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    fn parse(self, content: &str) -> Result<Config, String> {
+        match self {
+            Self::Ron => ron::from_str(content).map_err(|e| e.to_string()),
+            Self::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            Self::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Ron => "RON",
+            Self::Toml => "TOML",
+            Self::Json => "JSON",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read {
+        path: String,
+        error: std::io::Error,
+    },
+    Parse {
+        source: String,
+        errors: Vec<(ConfigFormat, String)>,
+    },
+    Invalid {
+        issues: Vec<ConfigIssue>,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read { path, error } => {
+                write!(f, "cannot read configuration file '{path}': {error}")
+            }
+            Self::Parse { source, errors } => {
+                write!(f, "cannot parse configuration from {source}")?;
+                for (format, error) in errors {
+                    write!(f, "\n  as {format}: {error}")?;
+                }
+                Ok(())
+            }
+            Self::Invalid { issues } => {
+                write!(f, "invalid configuration")?;
+                for issue in issues {
+                    write!(f, "\n  - {issue}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    /// Issues of an invalid configuration, none when it was not read or parsed.
+    pub fn issues(&self) -> &[ConfigIssue] {
+        match self {
+            Self::Invalid { issues } => issues,
+            _ => &[],
+        }
+    }
+}
+
+/// Parses a configuration passed inline on the command line, always RON.
+pub fn from_inline(content: &str) -> Result<Config, ConfigError> {
+    ConfigFormat::Ron
+        .parse(content)
+        .map_err(|error| ConfigError::Parse {
+            source: "--configuration argument".into(),
+            errors: vec![(ConfigFormat::Ron, error)],
+        })
+}
+
+/// Reads a configuration file, its format is taken from the extension
+/// and every supported format is tried if the extension is unknown.
+pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(|error| ConfigError::Read {
+        path: path.display().to_string(),
+        error,
+    })?;
+
+    let formats = match ConfigFormat::from_extension(path) {
+        Some(format) => vec![format],
+        None => ConfigFormat::ALL.to_vec(),
+    };
+
+    let mut errors = Vec::new();
+    for format in formats {
+        match format.parse(&content) {
+            Ok(config) => return Ok(config),
+            Err(error) => errors.push((format, error)),
+        }
+    }
+
+    Err(ConfigError::Parse {
+        source: format!("file '{}'", path.display()),
+        errors,
+    })
+}
+
+/// Checks everything that can be verified before the first test is started.
+pub fn validate(config: &Config) -> Result<(), ConfigError> {
+    let mut issues = Vec::new();
+
+    match config.running_mode() {
+        RunningMode::Local {
+            in_test_path,
+            out_test_path,
+            err_test_path,
+            compiled_program_path,
+        } => {
+            let directories = [
+                ("in_test_path", in_test_path),
+                ("out_test_path", out_test_path),
+                ("err_test_path", err_test_path),
+            ];
+
+            for (field, path) in directories {
+                if !Path::new(path).is_dir() {
+                    issues.push(ConfigIssue::MissingDirectory {
+                        field: field.into(),
+                        path: path.clone(),
+                    });
+                }
+            }
+
+            if !Path::new(compiled_program_path).is_file() {
+                issues.push(ConfigIssue::MissingProgram {
+                    path: compiled_program_path.clone(),
+                });
+            }
+        }
+        mode => issues.push(ConfigIssue::UnsupportedMode {
+            mode: mode.to_string(),
+        }),
+    }
+
+    let mut seen = HashSet::new();
+    for name in config.test_names() {
+        if !seen.insert(name) {
+            issues.push(ConfigIssue::DuplicateTest { name: name.clone() });
+        }
+    }
+
+    if let Err(e) = TestFilter::new(config.filters()) {
+        issues.push(ConfigIssue::InvalidPattern {
+            error: e.to_string(),
+        });
+    }
+
+    // Anything else would make converting the limit to a duration panic.
+    for (field, time) in config.time_limits() {
+        if time <= 0.0 || Duration::try_from_secs_f64(time).is_err() {
+            issues.push(ConfigIssue::InvalidTimeLimit {
+                field,
+                time: time.to_string(),
+            });
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid { issues })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RON_CONFIG: &str = r#"(
+        mode: Local(
+            in_test_path: "{dir}",
+            out_test_path: "{dir}",
+            err_test_path: "{dir}/missing",
+            compiled_program_path: "{dir}/program",
+        ),
+        test_list: ["a", "b", "a"],
+        language: Cpp,
+        concurrency: Disabled,
+    )"#;
+
+    const TOML_CONFIG: &str = r#"
+        test_list = ["a"]
+        language = "Cpp"
+        concurrency = { Enabled = 2 }
+
+        [mode.Local]
+        in_test_path = "{dir}"
+        out_test_path = "{dir}"
+        err_test_path = "{dir}"
+        compiled_program_path = "{dir}/program"
+    "#;
+
+    const JSON_CONFIG: &str = r#"{
+        "mode": { "Local": {
+            "in_test_path": "{dir}",
+            "out_test_path": "{dir}",
+            "err_test_path": "{dir}",
+            "compiled_program_path": "{dir}/program"
+        } },
+        "test_list": ["a"],
+        "language": "Cpp",
+        "concurrency": "Disabled"
+    }"#;
+
+    fn write_config(dir: &Path, file_name: &str, template: &str) -> std::path::PathBuf {
+        let path = dir.join(file_name);
+        let content = template.replace("{dir}", dir.to_str().unwrap());
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn detects_format() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("program"), "").unwrap();
+
+        for (name, template) in [
+            ("config.ron", RON_CONFIG),
+            ("config.toml", TOML_CONFIG),
+            ("config.json", JSON_CONFIG),
+            ("config", TOML_CONFIG),
+            ("config.conf", JSON_CONFIG),
+        ] {
+            let path = write_config(dir.path(), name, template);
+            if let Err(e) = from_file(&path) {
+                panic!("{name}: {e}");
+            }
+        }
+
+        let path = write_config(dir.path(), "config.json", TOML_CONFIG);
+        assert!(matches!(
+            from_file(&path),
+            Err(ConfigError::Parse { errors, .. }) if errors.len() == 1
+        ));
+    }
+
+    #[test]
+    fn reports_every_issue() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path(), "config.ron", RON_CONFIG);
+        let config = from_file(&path).unwrap();
+
+        let issues = match validate(&config) {
+            Err(ConfigError::Invalid { issues }) => issues,
+            other => panic!("unexpected validation result {other:?}"),
+        };
+
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue::MissingDirectory {
+                    field: "err_test_path".into(),
+                    path: format!("{}/missing", dir.path().display()),
+                },
+                ConfigIssue::MissingProgram {
+                    path: format!("{}/program", dir.path().display()),
+                },
+                ConfigIssue::DuplicateTest { name: "a".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_time_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("program"), "").unwrap();
+        let template = format!(
+            r#"{TOML_CONFIG}
+        [limits]
+        time = -1.0
+        memory = 1024
+
+        [[groups]]
+        name = "slow"
+        tests = ["a"]
+        limits = {{ time = inf }}
+
+        [[groups]]
+        name = "fast"
+        tests = []
+        limits = {{ time = 0.5 }}
+
+        [test_limits.b]
+        time = nan
+
+        [test_limits.a]
+        time = 1e30
+        "#
+        );
+        let path = write_config(dir.path(), "config.toml", &template);
+        let config = from_file(&path).unwrap();
+
+        let issues = match validate(&config) {
+            Err(ConfigError::Invalid { issues }) => issues,
+            other => panic!("unexpected validation result {other:?}"),
+        };
+
+        let fields: Vec<_> = issues
+            .iter()
+            .map(|issue| match issue {
+                ConfigIssue::InvalidTimeLimit { field, .. } => field.as_str(),
+                other => panic!("unexpected issue {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "limits.time",
+                "groups.slow.limits.time",
+                "test_limits.a.time",
+                "test_limits.b.time",
+            ]
+        );
+    }
+}
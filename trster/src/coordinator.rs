@@ -0,0 +1,759 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{ChildStdin, Command, Stdio},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use trst_types::{
+    encode_frame, BackendMessage, Baseline, Concurrency, Config, CpuOptions, FailurePolicy,
+    FrameDecoder, FrontendMessage, SessionAddress, SessionClient, SessionRequest, SessionStream,
+    TestMeasure, TestResult,
+};
+
+use crate::testing::{self, Executor, Output};
+
+const READ_CHUNK: usize = 8192;
+/// Batches handed to every worker on average, smaller batches requeue less
+/// work when a worker dies.
+const BATCHES_PER_WORKER: usize = 4;
+
+/// Trster the coordinator hands tests to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorkerSpec {
+    /// Process started on this machine for every batch.
+    Local(PathBuf),
+    /// Session of `trster serve`, kept open across batches.
+    Server(SessionAddress),
+}
+
+impl FromStr for WorkerSpec {
+    type Err = String;
+
+    /// `local` is a process of this trster, `exec:PATH` a process of another
+    /// program and everything else an address of `trster serve`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "local" {
+            return std::env::current_exe()
+                .map(Self::Local)
+                .map_err(|e| format!("cannot find this trster: {e}"));
+        }
+        if let Some(path) = s.strip_prefix("exec:") {
+            return Ok(Self::Local(path.into()));
+        }
+
+        s.parse().map(Self::Server)
+    }
+}
+
+enum WorkerEvent {
+    Message(BackendMessage),
+    /// Request of the frontend, `None` once the control channel is closed.
+    Control(Option<FrontendMessage>),
+    BatchDone(usize),
+    /// The worker is gone, tests of its batch without a result are run elsewhere.
+    Died {
+        worker: usize,
+        unfinished: Vec<String>,
+        error: String,
+    },
+}
+
+/// Splits the selected tests into batches run by several workers and merges
+/// their results into one stream. Control frames are forwarded to the batches
+/// being run, the queued tests are held back or cancelled by the coordinator.
+pub struct Coordinator {
+    workers: Vec<WorkerSpec>,
+    token: String,
+    tests: Vec<String>,
+    excluded: Vec<String>,
+}
+
+impl Coordinator {
+    /// `token` authenticates the sessions of server workers.
+    pub fn new(
+        config: &Config,
+        only: Option<&HashSet<String>>,
+        workers: Vec<WorkerSpec>,
+        token: String,
+    ) -> Self {
+        let (selected, excluded) = testing::select_tests(config, only);
+
+        Self {
+            workers,
+            token,
+            tests: selected.into_iter().cloned().collect(),
+            excluded,
+        }
+    }
+}
+
+impl Executor for Coordinator {
+    fn execute_testing<R: Read + Send + 'static, O: Output>(
+        self,
+        config: &Config,
+        baseline: Option<&Baseline>,
+        control: R,
+        output: &mut O,
+    ) -> Vec<TestResult> {
+        output.emit(testing::hello());
+
+        let batch_size = self
+            .tests
+            .len()
+            .div_ceil(self.workers.len() * BATCHES_PER_WORKER)
+            .max(1);
+        let mut queue: VecDeque<String> = self.tests.into();
+        let (events_sender, events) = mpsc::channel();
+
+        let local_workers = self
+            .workers
+            .iter()
+            .filter(|spec| matches!(spec, WorkerSpec::Local(_)))
+            .count();
+        let local_config = shared_machine_config(config, local_workers);
+
+        let requests = Arc::new(Requests::default());
+        let slots: Vec<ControlSlot> = self
+            .workers
+            .iter()
+            .map(|_| ControlSlot::default())
+            .collect();
+
+        let mut workers: Vec<Option<Sender<Vec<String>>>> = self
+            .workers
+            .into_iter()
+            .enumerate()
+            .map(|(id, spec)| {
+                let (batches_sender, batches) = mpsc::channel();
+                let config = match spec {
+                    WorkerSpec::Local(_) => local_config.clone(),
+                    WorkerSpec::Server(_) => config.clone(),
+                };
+                spawn_worker(
+                    id,
+                    spec,
+                    self.token.clone(),
+                    config,
+                    batches,
+                    events_sender.clone(),
+                    (slots[id].clone(), requests.clone()),
+                );
+                Some(batches_sender)
+            })
+            .collect();
+
+        let control_events = events_sender;
+        testing::spawn_control_listener(control, move |message| {
+            control_events.send(WorkerEvent::Control(message)).is_ok()
+        });
+
+        let mut idle: VecDeque<usize> = (0..workers.len()).collect();
+        let mut busy = 0;
+        let mut results = Vec::new();
+        let mut stops = Stops::default();
+
+        loop {
+            while !queue.is_empty() && !requests.paused.load(Ordering::SeqCst) {
+                let Some(worker) = idle.pop_front() else {
+                    break;
+                };
+
+                let batch = queue.drain(..batch_size.min(queue.len())).collect();
+                let sender = workers[worker].as_ref().expect("idle workers are alive");
+                // A worker only stops listening after it reported its death.
+                let _ = sender.send(batch);
+                busy += 1;
+            }
+
+            // A paused run with idle workers waits for the frontend to resume it.
+            if busy == 0 && (queue.is_empty() || idle.is_empty()) {
+                break;
+            }
+
+            let Ok(event) = events.recv() else {
+                log::error!("Every worker is gone without reporting");
+                break;
+            };
+
+            match event {
+                WorkerEvent::Message(message) => {
+                    if let BackendMessage::TestCompleted { result } = &message {
+                        results.push(result.clone());
+                        if result.outcome().is_failure() {
+                            stops.failed(config, result.name());
+                        }
+                    }
+                    output.emit(message);
+                }
+                WorkerEvent::Control(Some(FrontendMessage::Cancel)) => {
+                    requests.cancelled.store(true, Ordering::SeqCst);
+                    broadcast(&slots, &FrontendMessage::Cancel);
+                    stops.cancelled = true;
+                }
+                WorkerEvent::Control(Some(FrontendMessage::CancelTest { name })) => {
+                    match queue.iter().position(|queued| *queued == name) {
+                        Some(position) => {
+                            queue.remove(position);
+                            let result = TestResult::new(name, TestMeasure::Cancelled);
+                            results.push(result.clone());
+                            output.emit(BackendMessage::TestCompleted { result });
+                        }
+                        None => broadcast(&slots, &FrontendMessage::CancelTest { name }),
+                    }
+                }
+                WorkerEvent::Control(Some(FrontendMessage::Pause)) => {
+                    requests.paused.store(true, Ordering::SeqCst);
+                    broadcast(&slots, &FrontendMessage::Pause);
+                }
+                // Nobody is left to resume the testing once the control channel is closed.
+                WorkerEvent::Control(Some(FrontendMessage::Resume) | None) => {
+                    if requests.paused.swap(false, Ordering::SeqCst) {
+                        broadcast(&slots, &FrontendMessage::Resume);
+                    }
+                }
+                WorkerEvent::Control(Some(FrontendMessage::SetConcurrency(_))) => {
+                    log::warn!("The concurrency of a run split across workers cannot be changed");
+                }
+                WorkerEvent::BatchDone(worker) => {
+                    busy -= 1;
+                    idle.push_back(worker);
+                }
+                WorkerEvent::Died {
+                    worker,
+                    unfinished,
+                    error,
+                } => {
+                    log::warn!("Worker {worker} died, requeueing its tests. Error = {error}");
+                    busy -= 1;
+                    workers[worker] = None;
+                    queue.extend(unfinished);
+                }
+            }
+
+            // Batches already handed out finish on their own, queued tests are not started.
+            let (stopped, kept) = queue
+                .drain(..)
+                .partition::<VecDeque<_>, _>(|name| stops.outcome(config, name).is_some());
+            queue = kept;
+            for name in stopped {
+                let outcome = stops.outcome(config, &name).unwrap();
+                let result = TestResult::new(name, outcome);
+                results.push(result.clone());
+                output.emit(BackendMessage::TestCompleted { result });
+            }
+        }
+
+        for name in queue {
+            let reason = "every worker died".to_string();
+            let result = TestResult::new(name, TestMeasure::Skipped { reason });
+            results.push(result.clone());
+            output.emit(BackendMessage::TestCompleted { result });
+        }
+
+        // Idle workers finish once their batch channel is closed.
+        drop(workers);
+
+        log::trace!("Testing process done, emitting end message");
+        testing::finish_run(config, baseline, &results, self.excluded, output);
+
+        results
+    }
+}
+
+/// Cancellation, and failures which under the failure policy of the run,
+/// keep queued tests from starting.
+#[derive(Default)]
+struct Stops {
+    cancelled: bool,
+    fail_fast: Option<String>,
+    /// Reasons of the stopped groups by their names.
+    groups: HashMap<String, String>,
+}
+
+impl Stops {
+    fn failed(&mut self, config: &Config, name: &str) {
+        match config.failure_policy() {
+            FailurePolicy::FailFast if self.fail_fast.is_none() => {
+                self.fail_fast = Some(format!("fail-fast after test {name} failed"));
+            }
+            FailurePolicy::StopGroup => {
+                if let Some(group) = config.group_of(name) {
+                    self.groups
+                        .entry(group.name().to_string())
+                        .or_insert_with(|| format!("test {name} of group {} failed", group.name()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Outcome reported instead of running the test `name`, if it is stopped.
+    fn outcome(&self, config: &Config, name: &str) -> Option<TestMeasure> {
+        if self.cancelled {
+            return Some(TestMeasure::Cancelled);
+        }
+
+        let reason = self.fail_fast.clone().or_else(|| {
+            let group = config.group_of(name)?;
+            self.groups.get(group.name()).cloned()
+        })?;
+        Some(TestMeasure::Skipped { reason })
+    }
+}
+
+/// Requests of the frontend which also apply to batches started after them.
+#[derive(Default)]
+struct Requests {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+/// Connection to the batch a worker runs, `None` between batches.
+type ControlSlot = Arc<Mutex<Option<BatchControl>>>;
+
+enum BatchControl {
+    /// Standard input of a local trster.
+    Local(ChildStdin),
+    /// Second handle of the session of `trster serve`.
+    Server(SessionStream),
+}
+
+impl BatchControl {
+    fn send(&mut self, message: &FrontendMessage) -> std::io::Result<()> {
+        match self {
+            Self::Local(stdin) => {
+                stdin.write_all(&encode_frame(message))?;
+                stdin.flush()
+            }
+            Self::Server(stream) => {
+                stream.write_all(&encode_frame(&SessionRequest::Control(message.clone())))?;
+                stream.flush()
+            }
+        }
+    }
+}
+
+/// Makes a starting batch reachable by control frames, it catches up with a
+/// cancellation or pause requested before it started.
+fn attach(slot: &ControlSlot, requests: &Requests, mut control: BatchControl) {
+    let mut slot = slot.lock().unwrap();
+
+    let missed = if requests.cancelled.load(Ordering::SeqCst) {
+        Some(FrontendMessage::Cancel)
+    } else if requests.paused.load(Ordering::SeqCst) {
+        Some(FrontendMessage::Pause)
+    } else {
+        None
+    };
+    if let Some(message) = missed {
+        if let Err(e) = control.send(&message) {
+            log::warn!("Cannot control a batch. Error = {e}");
+        }
+    }
+
+    *slot = Some(control);
+}
+
+fn broadcast(slots: &[ControlSlot], message: &FrontendMessage) {
+    for slot in slots {
+        if let Some(control) = slot.lock().unwrap().as_mut() {
+            // A batch which already ended is noticed by its worker.
+            if let Err(e) = control.send(message) {
+                log::warn!("Cannot control a batch. Error = {e}");
+            }
+        }
+    }
+}
+
+/// Configuration of `workers` local workers, which split the cores of this
+/// machine instead of each using all of them. Pinning is left to a single
+/// local worker, several would pin their tests to the same cores.
+fn shared_machine_config(config: &Config, workers: usize) -> Config {
+    if workers <= 1 {
+        return config.clone();
+    }
+
+    let cores = testing::usable_cores(config);
+    let threads = testing::concurrent_tests(config.concurrency_settings(), cores.len());
+    let share = (threads / workers).max(1) as u64;
+    let cpu = CpuOptions::new(false, config.cpu_options().skip_smt_siblings());
+
+    config
+        .clone()
+        .with_concurrency(Concurrency::Enabled(share))
+        .with_cpu_options(cpu)
+}
+
+fn spawn_worker(
+    id: usize,
+    spec: WorkerSpec,
+    token: String,
+    config: Config,
+    batches: Receiver<Vec<String>>,
+    events: Sender<WorkerEvent>,
+    (slot, requests): (ControlSlot, Arc<Requests>),
+) {
+    std::thread::spawn(move || {
+        let mut session = None;
+
+        for batch in batches {
+            let mut unfinished: HashSet<String> = batch.iter().cloned().collect();
+            let config = config.clone().with_test_list(batch);
+
+            // The coordinator reports the start and end of the whole run itself.
+            let forward = |message: BackendMessage| match &message {
+                BackendMessage::ExecutionStarted { .. } => {
+                    let _ = events.send(WorkerEvent::Message(message));
+                }
+                BackendMessage::TestCompleted { result } => {
+                    unfinished.remove(result.name());
+                    let _ = events.send(WorkerEvent::Message(message));
+                }
+                _ => {}
+            };
+
+            let attach = |control| attach(&slot, &requests, control);
+            let ran = match &spec {
+                WorkerSpec::Local(program) => run_local(program, &config, attach, forward),
+                WorkerSpec::Server(address) => {
+                    run_server(&mut session, address, &token, &config, attach, forward)
+                }
+            };
+            slot.lock().unwrap().take();
+
+            let error = match ran {
+                Ok(()) if unfinished.is_empty() => {
+                    let _ = events.send(WorkerEvent::BatchDone(id));
+                    continue;
+                }
+                Ok(()) => "some tests of the batch were not reported".to_string(),
+                Err(e) => e.to_string(),
+            };
+
+            let _ = events.send(WorkerEvent::Died {
+                worker: id,
+                unfinished: unfinished.into_iter().collect(),
+                error,
+            });
+            return;
+        }
+    });
+}
+
+/// Runs a batch in a trster process, whose frames are read from its standard
+/// output. Its standard input is handed to `attach` for control frames.
+fn run_local(
+    program: &Path,
+    config: &Config,
+    attach: impl FnOnce(BatchControl),
+    mut on_message: impl FnMut(BackendMessage),
+) -> std::io::Result<()> {
+    let configuration = ron::to_string(config).map_err(std::io::Error::other)?;
+
+    // Its logs end up next to the ones of the coordinator.
+    let mut child = Command::new(program)
+        .args(["--format", "binary", "--configuration", &configuration])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    let mut stdout = child.stdout.take().unwrap();
+    attach(BatchControl::Local(child.stdin.take().unwrap()));
+
+    let mut decoder = FrameDecoder::<BackendMessage>::new();
+    let mut chunk = [0; READ_CHUNK];
+    let mut greeted = false;
+    let mut completed = false;
+    let mut rejected = None;
+    loop {
+        let read = stdout.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+
+        decoder.push(&chunk[..read]);
+        while let Some(message) = decoder.next_message() {
+            match message {
+                Ok(message) => {
+                    if !greeted {
+                        if let Err(e) = message.clone().accept_hello() {
+                            let _ = child.kill();
+                            let _ = child.wait();
+                            return Err(std::io::Error::other(e.to_string()));
+                        }
+                        greeted = true;
+                    }
+                    completed |= message == BackendMessage::TestingProcessCompleted;
+                    if let BackendMessage::ConfigRejected { description, .. } = &message {
+                        rejected = Some(description.clone());
+                    }
+                    on_message(message);
+                }
+                Err(e) => log::error!("Invalid frame of a worker. Error = {e}"),
+            }
+        }
+        if decoder.is_failed() {
+            let _ = child.kill();
+            break;
+        }
+    }
+
+    // Failing tests also make trster exit with an error.
+    let status = child.wait()?;
+    if completed {
+        Ok(())
+    } else if let Some(description) = rejected {
+        Err(std::io::Error::other(description))
+    } else {
+        Err(std::io::Error::other(format!(
+            "worker exited with {status} before completing"
+        )))
+    }
+}
+
+/// Uploads a batch to a session of `trster serve` and runs it there,
+/// connecting first if there is no session yet.
+fn run_server(
+    session: &mut Option<SessionClient<SessionStream>>,
+    address: &SessionAddress,
+    token: &str,
+    config: &Config,
+    attach: impl FnOnce(BatchControl),
+    on_message: impl FnMut(BackendMessage),
+) -> std::io::Result<()> {
+    if session.is_none() {
+        let stream = SessionStream::connect(address)?;
+        *session = Some(SessionClient::new(stream, token)?);
+    }
+    let client = session.as_mut().unwrap();
+
+    let remote = client.upload_local(config)?;
+    client.start_run(remote)?;
+    attach(BatchControl::Server(client.control_stream()?));
+    client.finish_run(on_message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    use super::*;
+    use crate::{
+        serve,
+        test_support::{local_config, script},
+    };
+
+    const TOKEN: &str = "secret";
+
+    fn serve_worker(dir: &Path, name: &str) -> WorkerSpec {
+        let path = dir.join(name);
+        let listener = UnixListener::bind(&path).unwrap();
+        let work_dir = dir.to_path_buf();
+
+        std::thread::spawn(move || {
+            let pending = serve::PendingConnections::default();
+            for stream in listener.incoming() {
+                let (work_dir, guard) = (work_dir.clone(), pending.enter().unwrap());
+                let stream = SessionStream::Unix(stream.unwrap());
+                std::thread::spawn(move || {
+                    serve::serve_connection(stream, TOKEN, &work_dir, guard)
+                });
+            }
+        });
+
+        WorkerSpec::Server(SessionAddress::Unix(path))
+    }
+
+    /// Tests doubling their input, with a program doing so.
+    fn doubling_tests(dir: &Path, count: usize) -> Config {
+        let program = script(dir, "program", "#!/bin/sh\nread n; echo $((n * 2))\n");
+        let tests = dir.join("tests");
+        std::fs::create_dir(&tests).unwrap();
+
+        let names: Vec<String> = (0..count).map(|i| format!("t{i}")).collect();
+        for (i, name) in names.iter().enumerate() {
+            std::fs::write(tests.join(format!("{name}.in")), format!("{i}\n")).unwrap();
+            std::fs::write(tests.join(format!("{name}.out")), format!("{}\n", i * 2)).unwrap();
+            std::fs::write(tests.join(format!("{name}.err")), "").unwrap();
+        }
+
+        local_config(tests.to_str().unwrap(), &program, names)
+    }
+
+    fn run(config: &Config, workers: Vec<WorkerSpec>) -> (Vec<TestResult>, Vec<u8>) {
+        let coordinator = Coordinator::new(config, None, workers, TOKEN.into());
+        let mut output = Vec::new();
+        let results = coordinator.execute_testing(config, None, std::io::empty(), &mut output);
+
+        (results, output)
+    }
+
+    #[test]
+    fn merges_results_of_several_workers() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = doubling_tests(dir.path(), 12);
+        let workers = vec![
+            serve_worker(dir.path(), "a.sock"),
+            serve_worker(dir.path(), "b.sock"),
+            serve_worker(dir.path(), "c.sock"),
+        ];
+
+        let (results, output) = run(&config, workers);
+
+        let mut names: Vec<_> = results.iter().map(|result| result.name()).collect();
+        names.sort_unstable();
+        let mut expected: Vec<_> = config.test_names().map(String::as_str).collect();
+        expected.sort_unstable();
+        assert_eq!(names, expected);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result.outcome(), TestMeasure::Success { .. })));
+
+        let mut decoder = FrameDecoder::<BackendMessage>::new();
+        decoder.push(&output);
+        let messages: Vec<_> = std::iter::from_fn(|| decoder.next_message())
+            .map(Result::unwrap)
+            .collect();
+        let count = |pattern: fn(&BackendMessage) -> bool| {
+            messages.iter().filter(|message| pattern(message)).count()
+        };
+        assert!(matches!(messages[0], BackendMessage::Hello { .. }));
+        assert_eq!(count(|m| matches!(m, BackendMessage::Hello { .. })), 1);
+        assert_eq!(count(|m| matches!(m, BackendMessage::Summary { .. })), 1);
+        assert_eq!(
+            messages.last(),
+            Some(&BackendMessage::TestingProcessCompleted)
+        );
+    }
+
+    #[test]
+    fn requeues_tests_of_dead_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = doubling_tests(dir.path(), 8);
+        let dying = script(dir.path(), "dying", "#!/bin/sh\nexit 3\n");
+        let workers = vec![
+            WorkerSpec::Local(dying.into()),
+            serve_worker(dir.path(), "a.sock"),
+            WorkerSpec::Server(SessionAddress::Unix(dir.path().join("missing.sock"))),
+        ];
+
+        let (results, _) = run(&config, workers);
+
+        assert_eq!(results.len(), 8);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result.outcome(), TestMeasure::Success { .. })));
+    }
+
+    #[test]
+    fn cancel_reaches_running_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = doubling_tests(dir.path(), 8);
+        script(dir.path(), "program", "#!/bin/sh\nsleep 30\n");
+        let workers = vec![
+            serve_worker(dir.path(), "a.sock"),
+            serve_worker(dir.path(), "b.sock"),
+        ];
+        let (mut frontend, control) = UnixStream::pair().unwrap();
+        frontend
+            .write_all(&encode_frame(&FrontendMessage::Cancel))
+            .unwrap();
+
+        let coordinator = Coordinator::new(&config, None, workers, TOKEN.into());
+        let results = coordinator.execute_testing(&config, None, control, &mut Vec::new());
+
+        assert_eq!(results.len(), 8);
+        assert!(results
+            .iter()
+            .all(|result| *result.outcome() == TestMeasure::Cancelled));
+    }
+
+    #[test]
+    fn fail_fast_skips_queued_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = doubling_tests(dir.path(), 12).with_failure_policy(FailurePolicy::FailFast);
+        std::fs::write(dir.path().join("tests/t0.out"), "wrong\n").unwrap();
+
+        let (results, _) = run(&config, vec![serve_worker(dir.path(), "a.sock")]);
+
+        assert_eq!(results.len(), 12);
+        assert!(results[0].outcome().is_failure());
+        assert!(results[1..].iter().all(|result| *result.outcome()
+            == TestMeasure::Skipped {
+                reason: "fail-fast after test t0 failed".into()
+            }));
+    }
+
+    #[test]
+    fn local_workers_share_cores() {
+        let config = local_config("t", "p", Vec::new()).with_concurrency(Concurrency::Enabled(4));
+
+        let single = shared_machine_config(&config, 1);
+        assert_eq!(*single.concurrency_settings(), Concurrency::Enabled(4));
+        assert!(single.cpu_options().pin_tests());
+
+        let shared = shared_machine_config(&config, 3);
+        assert_eq!(*shared.concurrency_settings(), Concurrency::Enabled(1));
+        assert!(!shared.cpu_options().pin_tests());
+    }
+
+    #[test]
+    fn skips_tests_once_every_worker_died() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = doubling_tests(dir.path(), 3);
+        let dying = script(dir.path(), "dying", "#!/bin/sh\nexit 3\n");
+
+        let (results, _) = run(&config, vec![WorkerSpec::Local(dying.into())]);
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| *result.outcome()
+            == TestMeasure::Skipped {
+                reason: "every worker died".into()
+            }));
+    }
+
+    #[test]
+    fn worker_without_hello_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = doubling_tests(dir.path(), 1);
+        // A lone TestingProcessCompleted frame.
+        let worker = script(
+            dir.path(),
+            "worker",
+            "#!/bin/sh\nprintf '\\0\\0\\0\\1\\3'\n",
+        );
+
+        let error = run_local(Path::new(&worker), &config, |_| {}, |_| {}).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "stream does not start with a hello frame"
+        );
+    }
+
+    #[test]
+    fn worker_specs() {
+        assert_eq!(
+            "exec:/bin/trster".parse(),
+            Ok(WorkerSpec::Local("/bin/trster".into()))
+        );
+        assert_eq!(
+            "10.0.0.2:7070".parse(),
+            Ok(WorkerSpec::Server(SessionAddress::Tcp(
+                "10.0.0.2:7070".into()
+            )))
+        );
+        assert_eq!(
+            "unix:w.sock".parse(),
+            Ok(WorkerSpec::Server(SessionAddress::Unix("w.sock".into())))
+        );
+        assert!(matches!("local".parse(), Ok(WorkerSpec::Local(_))));
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use trst_types::Limits;
+
+use crate::testing::{self, Termination};
+
+/// Lines of context around the changed lines of an expected file.
+const DIFF_CONTEXT: usize = 2;
+
+#[derive(clap::Args, Debug)]
+pub struct GenerateArgs {
+    /// Trusted solution whose outputs become the expected ones
+    #[arg(long)]
+    reference: String,
+
+    /// Directory holding the `.in` files
+    #[arg(long)]
+    in_dir: PathBuf,
+
+    /// Directory of the `.out` files, the input directory by default
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Directory of the `.err` files, the output directory by default
+    #[arg(long)]
+    err_dir: Option<PathBuf>,
+
+    /// Time limit of the reference on every input in seconds
+    #[arg(long, default_value_t = 10.0)]
+    time_limit: f64,
+
+    /// Write the changed expected files, otherwise they are only shown
+    #[arg(long)]
+    write: bool,
+}
+
+/// Expected file whose content differs from the output of the reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub path: PathBuf,
+    /// `None` when the file does not exist yet.
+    pub old: Option<String>,
+    pub new: String,
+}
+
+impl Change {
+    /// Unified diff of the change, a new file is diffed against an empty one.
+    pub fn diff(&self) -> String {
+        let path = self.path.display().to_string();
+        let old = self.old.as_deref().unwrap_or_default();
+
+        similar::TextDiff::from_lines(old, &self.new)
+            .unified_diff()
+            .context_radius(DIFF_CONTEXT)
+            .header(
+                if self.old.is_some() {
+                    &path
+                } else {
+                    "/dev/null"
+                },
+                &path,
+            )
+            .to_string()
+    }
+}
+
+pub fn run(args: &GenerateArgs) -> ExitCode {
+    let out_dir = args.out_dir.as_ref().unwrap_or(&args.in_dir);
+    let err_dir = args.err_dir.as_ref().unwrap_or(out_dir);
+    let limits = Limits::new(args.time_limit, None, None);
+
+    let (changes, unchanged) =
+        match expected_changes(&args.reference, &args.in_dir, out_dir, err_dir, &limits) {
+            Ok(plan) => plan,
+            Err(e) => {
+                eprintln!("error: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+    for change in &changes {
+        print!("{}", change.diff());
+    }
+
+    if args.write {
+        for change in &changes {
+            let written = match change.path.parent() {
+                Some(dir) => std::fs::create_dir_all(dir),
+                None => Ok(()),
+            }
+            .and_then(|()| std::fs::write(&change.path, &change.new));
+            if let Err(e) = written {
+                eprintln!("error: cannot write '{}': {e}", change.path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+        println!(
+            "{} expected files written, {unchanged} unchanged",
+            changes.len()
+        );
+    } else {
+        println!(
+            "{} expected files would change, {unchanged} unchanged",
+            changes.len()
+        );
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Runs the reference on every `.in` file of `in_dir` and returns the
+/// expected files its outputs change, together with the number of unchanged
+/// ones. Nothing is returned when the reference fails on any input, its
+/// outputs would not be trustworthy.
+pub fn expected_changes(
+    reference: &str,
+    in_dir: &Path,
+    out_dir: &Path,
+    err_dir: &Path,
+    limits: &Limits,
+) -> Result<(Vec<Change>, usize), String> {
+    let entries = std::fs::read_dir(in_dir)
+        .map_err(|e| format!("cannot read '{}': {e}", in_dir.display()))?;
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "in"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort_unstable();
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    let mut failures = Vec::new();
+
+    for name in names {
+        let input_path = in_dir.join(format!("{name}.in"));
+        let input = std::fs::read(&input_path)
+            .map_err(|e| format!("cannot read '{}': {e}", input_path.display()))?;
+
+        let run = testing::run_program(reference, &[], input, limits)
+            .map_err(|e| format!("cannot run the reference '{reference}': {e}"))?;
+        let failure = match run.termination {
+            Termination::Exited(status) if status.success() => None,
+            Termination::Exited(status) => Some(status.to_string()),
+            Termination::TimeLimitExceeded => Some("time limit exceeded".into()),
+            termination => Some(format!("{termination:?}")),
+        };
+        if let Some(failure) = failure {
+            failures.push(format!("{name} ({failure})"));
+            continue;
+        }
+
+        for (dir, extension, output) in [(out_dir, "out", run.stdout), (err_dir, "err", run.stderr)]
+        {
+            let path = dir.join(format!("{name}.{extension}"));
+            let new = String::from_utf8_lossy(&output).into_owned();
+            let old = std::fs::read_to_string(&path).ok();
+
+            if old.as_ref() == Some(&new) {
+                unchanged += 1;
+            } else {
+                changes.push(Change { path, old, new });
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(format!("the reference failed on {}", failures.join(", ")));
+    }
+
+    Ok((changes, unchanged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::script;
+
+    #[test]
+    fn changes_of_expected_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let tests = dir.path().join("tests");
+        std::fs::create_dir(&tests).unwrap();
+        for (name, input) in [("a", "1\n"), ("b", "2\n"), ("c", "3\n")] {
+            std::fs::write(tests.join(format!("{name}.in")), input).unwrap();
+        }
+        std::fs::write(tests.join("a.out"), "2\n").unwrap();
+        std::fs::write(tests.join("a.err"), "").unwrap();
+        std::fs::write(tests.join("b.out"), "5\n").unwrap();
+        let reference = script(
+            dir.path(),
+            "reference",
+            "#!/bin/sh\nread n; echo $((n * 2))\n",
+        );
+
+        let (changes, unchanged) = expected_changes(
+            &reference,
+            &tests,
+            &tests,
+            &tests,
+            &Limits::new(5.0, None, None),
+        )
+        .unwrap();
+
+        assert_eq!(unchanged, 2);
+        let paths: Vec<_> = changes
+            .iter()
+            .map(|change| change.path.strip_prefix(&tests).unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(paths, ["b.out", "b.err", "c.out", "c.err"]);
+        assert_eq!(changes[0].old.as_deref(), Some("5\n"));
+        assert_eq!(changes[0].new, "4\n");
+        assert!(changes[0].diff().contains("-5\n+4\n"));
+        assert!(changes[2].diff().starts_with("--- /dev/null\n"));
+    }
+
+    #[test]
+    fn refuses_failing_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["crash", "fine", "slow"] {
+            std::fs::write(dir.path().join(format!("{name}.in")), format!("{name}\n")).unwrap();
+        }
+        let reference = script(
+            dir.path(),
+            "reference",
+            "#!/bin/sh\nread name\n[ $name = slow ] && sleep 5\n[ $name = crash ] && exit 3\necho ok\n",
+        );
+
+        let error = expected_changes(
+            &reference,
+            dir.path(),
+            dir.path(),
+            dir.path(),
+            &Limits::new(0.5, None, None),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            "the reference failed on crash (exit status: 3), slow (time limit exceeded)"
+        );
+        assert!(!dir.path().join("fine.out").exists());
+    }
+}
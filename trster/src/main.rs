@@ -1,48 +1,290 @@
+mod compare;
+mod config;
+mod coordinator;
+mod generate;
+mod minimize;
+mod pretty;
+mod report;
+mod results;
+mod serve;
+mod stress;
+#[cfg(test)]
+mod test_support;
 mod testing;
-use std::time::Duration;
 
-use clap::Parser;
-use serde::{Deserialize, Serialize};
-use testing::Paths;
+use std::{io::IsTerminal, path::PathBuf, process::ExitCode, time::Duration};
 
-use crate::testing::Executor;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::{
+    coordinator::{Coordinator, WorkerSpec},
+    pretty::{ExpectedOutputs, PrettyOutput},
+    report::ReportTarget,
+    testing::{Executor, Output},
+};
 pub use trst_types::*;
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// Framed postcard stream read by the client
+    Binary,
+    /// Colored report for people
+    Pretty,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two runs recorded in the run history test by test
+    Compare(compare::CompareArgs),
+    /// Serve testing sessions of clients on a TCP address or Unix socket
+    Serve(serve::ServeArgs),
+    /// Compare the program with a reference on generated inputs until they disagree
+    Stress(stress::StressArgs),
+    /// Shrink the input of a failing test while it keeps failing the same way
+    Minimize(minimize::MinimizeArgs),
+    /// Produce or refresh expected outputs by running a reference on every input
+    GenerateOutputs(generate::GenerateArgs),
+}
+
 /// Program used by trst program in order to conduct tests
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Configuration in RON format
-    #[arg(long, short)]
-    configuration: String,
+    #[arg(long, short, required_unless_present = "config_file")]
+    configuration: Option<String>,
+
+    /// Path to configuration file in RON, TOML or JSON format
+    #[arg(long, conflicts_with = "configuration")]
+    config_file: Option<PathBuf>,
 
-    /// Timeout limit for test
+    /// File the results are saved to as JSON after testing
     #[arg(long)]
-    timeout: f64,
-}
+    results_file: Option<PathBuf>,
+
+    /// Run only tests which failed according to the results file
+    #[arg(long, requires = "results_file")]
+    rerun_failed: bool,
+
+    /// Baseline file the timing and memory of passing tests are compared with
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Baseline file updated with timing and memory of passing tests after testing
+    #[arg(long)]
+    save_baseline: Option<PathBuf>,
 
-static TEST_TIMEOUT: once_cell::sync::OnceCell<Duration> = once_cell::sync::OnceCell::new();
+    /// Also write a report of the results, FORMAT is one of json, junit or tap
+    #[arg(long = "report", value_name = "FORMAT=PATH")]
+    reports: Vec<ReportTarget>,
 
-fn main() {
+    /// Directory of the run history the results are recorded in
+    #[arg(long)]
+    history_dir: Option<PathBuf>,
+
+    /// Number of newest runs kept in the history
+    #[arg(long, default_value_t = 100, requires = "history_dir")]
+    history_max_runs: usize,
+
+    /// Runs older than this number of days are removed from the history
+    #[arg(long, requires = "history_dir")]
+    history_max_age_days: Option<u64>,
+
+    /// Format of the standard output, pretty when it is a terminal and binary otherwise
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Split the tests across workers instead of running them here. A worker is
+    /// `local` for a process of this trster, `exec:PATH` for another trster or
+    /// an address of `trster serve`
+    #[arg(long = "worker", value_name = "WORKER")]
+    workers: Vec<WorkerSpec>,
+
+    /// File holding the token `trster serve` workers authenticate with
+    #[arg(long, requires = "workers")]
+    worker_token_file: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
     pretty_env_logger::init();
     let args = Args::parse();
-    let configuration_arg = args.configuration;
 
-    let config: Config = match ron::from_str(&configuration_arg) {
+    match args.command {
+        Some(Command::Compare(compare)) => compare::run(&compare),
+        Some(Command::Serve(serve)) => serve::run(&serve),
+        Some(Command::Stress(stress)) => stress::run(&stress),
+        Some(Command::Minimize(minimize)) => minimize::run(&minimize),
+        Some(Command::GenerateOutputs(generate)) => generate::run(&generate),
+        None => run_tests(args),
+    }
+}
+
+fn run_tests(args: Args) -> ExitCode {
+    let config = match args.config_file {
+        Some(path) => config::from_file(&path),
+        None => config::from_inline(&args.configuration.unwrap()),
+    };
+
+    let terminal = std::io::stdout().is_terminal();
+    let format = args.format.unwrap_or(if terminal {
+        OutputFormat::Pretty
+    } else {
+        OutputFormat::Binary
+    });
+
+    let config = match config.and_then(|config| config::validate(&config).map(|_| config)) {
         Err(e) => {
-            eprintln!(
-                "Error while converting rom to config: {}. ARG = ##[{}]##",
-                e, &configuration_arg
-            );
-            return;
+            // The client learns about the issues from the stream, people from the error output.
+            if format == OutputFormat::Binary {
+                let mut stdout = std::io::stdout();
+                stdout.emit(testing::hello());
+                stdout.emit(BackendMessage::ConfigRejected {
+                    description: e.to_string(),
+                    issues: e.issues().to_vec(),
+                });
+            }
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
         }
         Ok(config) => config,
     };
 
-    TEST_TIMEOUT
-        .set(Duration::from_secs_f64(args.timeout))
-        .unwrap();
+    let previous_failures = match &args.results_file {
+        Some(path) if args.rerun_failed => match results::load_failed(path) {
+            Ok(failed) => Some(failed),
+            Err(e) => {
+                eprintln!("error: cannot read results file '{}': {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => None,
+    };
 
-    let executor = testing::load_tests(&config);
+    let baseline = match &args.baseline {
+        Some(path) => match Baseline::load(path) {
+            Ok(baseline) => Some(baseline),
+            Err(e) => {
+                eprintln!("error: cannot read baseline file '{}': {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let needs_token = args
+        .workers
+        .iter()
+        .any(|worker| matches!(worker, WorkerSpec::Server(_)));
+    let token = match &args.worker_token_file {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(token) => token.trim().to_string(),
+            Err(e) => {
+                eprintln!("error: cannot read token file '{}': {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None if needs_token => {
+            eprintln!("error: workers of trster serve need --worker-token-file");
+            return ExitCode::FAILURE;
+        }
+        None => String::new(),
+    };
 
-    executor.execute_testing(&config);
+    let results = if args.workers.is_empty() {
+        let executor = testing::load_tests(&config, previous_failures.as_ref());
+        execute(executor, &config, baseline.as_ref(), format, terminal)
+    } else {
+        let workers = args.workers.clone();
+        let coordinator = Coordinator::new(&config, previous_failures.as_ref(), workers, token);
+        execute(coordinator, &config, baseline.as_ref(), format, terminal)
+    };
+
+    if let Some(path) = &args.results_file {
+        if let Err(e) = results::save(path, &results) {
+            eprintln!("error: cannot save results to '{}': {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    for report in &args.reports {
+        if let Err(e) = report.write(&results) {
+            eprintln!(
+                "error: cannot write {} report to '{}': {e}",
+                report.format,
+                report.path.display()
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(path) = &args.save_baseline {
+        if let Err(e) = Baseline::save_results(path, &results) {
+            eprintln!("error: cannot save baseline to '{}': {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(dir) = &args.history_dir {
+        let retention = Retention {
+            max_runs: Some(args.history_max_runs),
+            max_age: args
+                .history_max_age_days
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        };
+
+        match RunHistory::open(dir)
+            .and_then(|history| history.record(&config, &results, &retention))
+        {
+            Ok(0) => {}
+            Ok(removed) => log::info!("Removed {removed} runs from the history"),
+            Err(e) => {
+                eprintln!("error: cannot record the run in '{}': {e}", dir.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let all_passed = results
+        .iter()
+        .all(|result| matches!(result.outcome(), TestMeasure::Success { .. }));
+    if all_passed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn execute<E: Executor>(
+    executor: E,
+    config: &Config,
+    baseline: Option<&Baseline>,
+    format: OutputFormat,
+    terminal: bool,
+) -> Vec<TestResult> {
+    match format {
+        OutputFormat::Binary => {
+            executor.execute_testing(config, baseline, std::io::stdin(), &mut std::io::stdout())
+        }
+        OutputFormat::Pretty => {
+            let expected = match config.running_mode() {
+                RunningMode::Local {
+                    out_test_path,
+                    err_test_path,
+                    ..
+                } => Some(ExpectedOutputs {
+                    out_dir: out_test_path.into(),
+                    err_dir: err_test_path.into(),
+                }),
+                _ => None,
+            };
+            let color = terminal && std::env::var_os("NO_COLOR").is_none();
+            let mut output = PrettyOutput::new(std::io::stdout(), color, expected);
+
+            // Without a frontend there is nobody to send control frames.
+            executor.execute_testing(config, baseline, std::io::empty(), &mut output)
+        }
+    }
 }
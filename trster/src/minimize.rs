@@ -0,0 +1,383 @@
+use std::{io::IsTerminal, path::PathBuf, process::ExitCode};
+
+use trst_types::{BackendMessage, Config, Limits, TestMeasure};
+
+use crate::{
+    config,
+    pretty::PrettyOutput,
+    testing::{self, Output, Termination, TestingUnit},
+    OutputFormat,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct MinimizeArgs {
+    /// Configuration in RON format
+    #[arg(long, short, required_unless_present = "config_file")]
+    configuration: Option<String>,
+
+    /// Path to configuration file in RON, TOML or JSON format
+    #[arg(long, conflicts_with = "configuration")]
+    config_file: Option<PathBuf>,
+
+    /// Failing test whose input is shrunk
+    #[arg(long)]
+    test: String,
+
+    /// Trusted solution producing the expected outputs of smaller inputs,
+    /// needed for wrong answers
+    #[arg(long)]
+    reference: Option<String>,
+
+    /// Time limit of the reference in seconds
+    #[arg(long, default_value_t = 10.0)]
+    reference_time_limit: f64,
+
+    /// Most runs of the tested program tried before the smallest input so far is kept
+    #[arg(long, default_value_t = 1000)]
+    max_runs: u64,
+
+    /// File the minimized input is written to
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Format of the standard output, pretty when it is a terminal and binary otherwise
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+}
+
+/// Input which still makes the tested program fail the same way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minimized {
+    pub input: Vec<u8>,
+    pub original_size: u64,
+    pub measure: TestMeasure,
+}
+
+pub fn run(args: &MinimizeArgs) -> ExitCode {
+    let config = match &args.config_file {
+        Some(path) => config::from_file(path),
+        None => config::from_inline(args.configuration.as_ref().unwrap()),
+    };
+    let config = match config.and_then(|config| config::validate(&config).map(|_| config)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let terminal = std::io::stdout().is_terminal();
+    let format = args.format.unwrap_or(if terminal {
+        OutputFormat::Pretty
+    } else {
+        OutputFormat::Binary
+    });
+
+    match format {
+        OutputFormat::Binary => minimize_test(args, &config, &mut std::io::stdout()),
+        OutputFormat::Pretty => {
+            let color = terminal && std::env::var_os("NO_COLOR").is_none();
+            minimize_test(
+                args,
+                &config,
+                &mut PrettyOutput::new(std::io::stdout(), color, None),
+            )
+        }
+    }
+}
+
+fn minimize_test<O: Output>(args: &MinimizeArgs, config: &Config, output: &mut O) -> ExitCode {
+    let Some(unit) = testing::load_test(config, &args.test) else {
+        eprintln!("error: test '{}' is not in the configuration", args.test);
+        return ExitCode::FAILURE;
+    };
+    let reference = args.reference.as_ref().map(|program| Reference {
+        program: program.clone(),
+        limits: Limits::new(args.reference_time_limit, None, None),
+    });
+
+    output.emit(testing::hello());
+    output.emit(BackendMessage::ExecutionStarted {
+        test_name: args.test.clone(),
+    });
+
+    let minimized = match minimize(&unit, reference.as_ref(), args.max_runs) {
+        Ok(minimized) => minimized,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(path) = &args.output {
+        if let Err(e) = std::fs::write(path, &minimized.input) {
+            eprintln!("error: cannot write '{}': {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    output.emit(BackendMessage::InputMinimized {
+        test_name: args.test.clone(),
+        input: minimized.input,
+        original_size: minimized.original_size,
+        measure: minimized.measure,
+    });
+    output.emit(BackendMessage::TestingProcessCompleted);
+
+    ExitCode::SUCCESS
+}
+
+/// Trusted solution whose outputs are expected on inputs other than the one of the test.
+#[derive(Clone, Debug)]
+pub struct Reference {
+    pub program: String,
+    pub limits: Limits,
+}
+
+impl Reference {
+    /// Standard and error output on `input`, `None` when the reference does
+    /// not exit successfully, which means the input is not a valid one.
+    fn expected(&self, input: &[u8]) -> Result<Option<(String, String)>, String> {
+        let run = testing::run_program(&self.program, &[], input.to_vec(), &self.limits)
+            .map_err(|e| format!("cannot run the reference '{}': {e}", self.program))?;
+
+        Ok(match run.termination {
+            Termination::Exited(status) if status.success() => Some((
+                String::from_utf8_lossy(&run.stdout).into_owned(),
+                String::from_utf8_lossy(&run.stderr).into_owned(),
+            )),
+            _ => None,
+        })
+    }
+}
+
+/// Shrinks the input of a failing test by removing lines and then tokens,
+/// keeping every removal after which the program still fails the same way.
+/// Without a reference the expected files of the test are used, which only
+/// tells crashes and exceeded limits apart from other failures.
+pub fn minimize(
+    unit: &TestingUnit,
+    reference: Option<&Reference>,
+    max_runs: u64,
+) -> Result<Minimized, String> {
+    let original = unit
+        .input()
+        .map_err(|e| format!("cannot read the input of {}: {e}", unit.name()))?;
+
+    let judge = |input: &[u8]| -> Result<Option<TestMeasure>, String> {
+        let measure = match reference {
+            Some(reference) => match reference.expected(input)? {
+                Some((out, err)) => unit.run_input(input.to_vec(), Some((&out, &err))),
+                None => return Ok(None),
+            },
+            None => unit.run_input(input.to_vec(), None),
+        };
+        Ok(Some(measure))
+    };
+
+    let measure = match judge(&original)? {
+        Some(measure) if measure.is_failure() => measure,
+        Some(_) => return Err(format!("test {} does not fail", unit.name())),
+        None => return Err("the reference fails on the input of the test".into()),
+    };
+    match &measure {
+        TestMeasure::InternalProgramError { description } => {
+            return Err(format!("test {} cannot be run: {description}", unit.name()))
+        }
+        TestMeasure::Failure {
+            exit_status: Some(0),
+            ..
+        } if reference.is_none() => {
+            return Err("wrong answers can only be minimized with a reference".into())
+        }
+        _ => {}
+    }
+
+    let mut runs = 0;
+    let mut error = None;
+    let mut still_fails = |candidate: &[u8]| {
+        if runs >= max_runs || error.is_some() {
+            return false;
+        }
+        runs += 1;
+
+        match judge(candidate) {
+            Ok(candidate) => candidate.is_some_and(|candidate| same_failure(&candidate, &measure)),
+            Err(e) => {
+                error = Some(e);
+                false
+            }
+        }
+    };
+
+    let pieces = reduce(lines(&original), &mut still_fails);
+    let pieces = reduce(tokens(&pieces.concat()), &mut still_fails);
+    log::info!("Minimization done after {runs} runs");
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    Ok(Minimized {
+        input: pieces.concat(),
+        original_size: original.len() as u64,
+        measure,
+    })
+}
+
+/// Failures are the same when they have the same verdict, programs which
+/// did not pass their expected outputs must also exit the same way.
+fn same_failure(measure: &TestMeasure, original: &TestMeasure) -> bool {
+    match (measure, original) {
+        (
+            TestMeasure::Failure { exit_status, .. },
+            TestMeasure::Failure {
+                exit_status: original,
+                ..
+            },
+        ) => exit_status == original,
+        _ => std::mem::discriminant(measure) == std::mem::discriminant(original),
+    }
+}
+
+/// Lines of `input`, each with its line break.
+fn lines(input: &[u8]) -> Vec<Vec<u8>> {
+    input
+        .split_inclusive(|byte| *byte == b'\n')
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+/// Tokens of `input`, each with the whitespace after it. Leading whitespace
+/// is a piece of its own.
+fn tokens(input: &[u8]) -> Vec<Vec<u8>> {
+    let mut tokens: Vec<Vec<u8>> = Vec::new();
+
+    for (i, byte) in input.iter().enumerate() {
+        let starts_token =
+            i > 0 && input[i - 1].is_ascii_whitespace() && !byte.is_ascii_whitespace();
+        match tokens.last_mut() {
+            Some(token) if !starts_token => token.push(*byte),
+            _ => tokens.push(vec![*byte]),
+        }
+    }
+
+    tokens
+}
+
+/// Removes chunks of pieces, halving their size whenever none of them can be
+/// removed, until not even a single piece can be.
+fn reduce(mut pieces: Vec<Vec<u8>>, still_fails: &mut impl FnMut(&[u8]) -> bool) -> Vec<Vec<u8>> {
+    let mut size = (pieces.len() / 2).max(1);
+
+    while !pieces.is_empty() {
+        let mut removed = false;
+        let mut start = 0;
+
+        while start < pieces.len() {
+            let end = (start + size).min(pieces.len());
+            let candidate = [&pieces[..start], &pieces[end..]].concat().concat();
+
+            if still_fails(&candidate) {
+                pieces.drain(start..end);
+                removed = true;
+            } else {
+                start = end;
+            }
+        }
+
+        if !removed {
+            if size == 1 {
+                break;
+            }
+            size /= 2;
+        }
+        size = size.min(pieces.len()).max(1);
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::test_support::{local_config, script};
+
+    /// Test `big` of a program summing the numbers of its input.
+    fn setup(dir: &Path, program: &str, input: &str, expected: &str) -> TestingUnit {
+        let program = script(dir, "program", program);
+        std::fs::write(dir.join("big.in"), input).unwrap();
+        std::fs::write(dir.join("big.out"), expected).unwrap();
+        std::fs::write(dir.join("big.err"), "").unwrap();
+
+        let config = local_config(dir.to_str().unwrap(), &program, vec!["big".into()]);
+
+        testing::load_test(&config, "big").unwrap()
+    }
+
+    #[test]
+    fn splits_lines_and_tokens() {
+        assert_eq!(lines(b"1 2\n3\n4"), [&b"1 2\n"[..], b"3\n", b"4"]);
+        assert_eq!(tokens(b" 1  2\n3\n"), [&b" "[..], b"1  ", b"2\n", b"3\n"]);
+    }
+
+    #[test]
+    fn minimizes_crash_without_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        // Crashes whenever the input holds a 13.
+        let unit = setup(
+            dir.path(),
+            "#!/bin/sh\nif grep -qw 13; then exit 7; fi\necho 0\n",
+            "1 2 3\n4 5\n6 13 8\n9 10\n",
+            "0\n",
+        );
+
+        let minimized = minimize(&unit, None, 1000).unwrap();
+
+        assert_eq!(minimized.input, b"13 ");
+        assert_eq!(minimized.original_size, 22);
+        assert!(matches!(
+            minimized.measure,
+            TestMeasure::Failure {
+                exit_status: Some(7),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn minimizes_wrong_answer_with_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        // Sums the numbers but gets every number above 5 wrong.
+        let summing = "#!/bin/sh\ns=0; for n in $(cat); do s=$((s + n)); done; echo $s\n";
+        let unit = setup(
+            dir.path(),
+            "#!/bin/sh\ns=0; for n in $(cat); do [ $n -gt 5 ] && n=0; s=$((s + n)); done; echo $s\n",
+            "1 2\n3 9\n4\n",
+            "19\n",
+        );
+        let reference = Reference {
+            program: script(dir.path(), "reference", summing),
+            limits: Limits::new(5.0, None, None),
+        };
+
+        assert_eq!(
+            minimize(&unit, None, 1000).unwrap_err(),
+            "wrong answers can only be minimized with a reference"
+        );
+        let minimized = minimize(&unit, Some(&reference), 1000).unwrap();
+        assert_eq!(minimized.input, b"9\n");
+    }
+
+    #[test]
+    fn refuses_passing_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let unit = setup(dir.path(), "#!/bin/sh\necho 3\n", "1 2\n", "3\n");
+
+        assert_eq!(
+            minimize(&unit, None, 10).unwrap_err(),
+            "test big does not fail"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use trst_types::{BackendMessage, BaselineComparison, RunSummary, TestMeasure, TestResult};
+
+use crate::testing::Output;
+
+/// Longest diff printed for a failed test, in lines.
+const DIFF_LINES: usize = 20;
+
+#[derive(Clone, Copy)]
+enum Color {
+    Red,
+    Green,
+    Yellow,
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Red => "31",
+            Self::Green => "32",
+            Self::Yellow => "33",
+            Self::Dim => "2",
+        }
+    }
+}
+
+/// Directories holding the expected outputs, used to show diffs of failed tests.
+pub struct ExpectedOutputs {
+    pub out_dir: PathBuf,
+    pub err_dir: PathBuf,
+}
+
+/// Human readable report of the run for terminals.
+pub struct PrettyOutput<W: Write> {
+    out: W,
+    color: bool,
+    expected: Option<ExpectedOutputs>,
+    started: Instant,
+    failed: Vec<(String, &'static str)>,
+}
+
+impl<W: Write> PrettyOutput<W> {
+    pub fn new(out: W, color: bool, expected: Option<ExpectedOutputs>) -> Self {
+        Self {
+            out,
+            color,
+            expected,
+            started: Instant::now(),
+            failed: Vec::new(),
+        }
+    }
+
+    fn paint(&self, color: Color, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{text}\x1b[0m", color.code())
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn test_completed(&mut self, result: &TestResult) {
+        let outcome = result.outcome();
+        let (tag, color) = match outcome {
+            TestMeasure::Success { .. } => ("PASS", Color::Green),
+            TestMeasure::Failure { .. } => ("FAIL", Color::Red),
+            TestMeasure::InternalProgramError { .. } => ("ERROR", Color::Red),
+            TestMeasure::Timeout => ("TLE", Color::Red),
+            TestMeasure::MemoryLimitExceeded { .. } => ("MLE", Color::Red),
+            TestMeasure::OutputLimitExceeded => ("OLE", Color::Red),
+            TestMeasure::Cancelled => ("CANCEL", Color::Yellow),
+            TestMeasure::Skipped { .. } => ("SKIP", Color::Yellow),
+        };
+
+        let mut line = format!(
+            "{} {}",
+            self.paint(color, &format!("{tag:>6}")),
+            result.name()
+        );
+        if let Some(time) = result.time() {
+            line += &self.paint(Color::Dim, &format!(" {time:.3}s"));
+        }
+        if let Some(timing) = result.timing().filter(|timing| timing.runs > 1) {
+            line += &self.paint(
+                Color::Dim,
+                &format!(
+                    " ±{:.3}s over {} runs",
+                    timing.wall_time.stddev, timing.runs
+                ),
+            );
+        }
+        if result.is_flaky() {
+            line += &self.paint(Color::Yellow, " (flaky)");
+        }
+        let _ = writeln!(self.out, "{line}");
+
+        match outcome {
+            TestMeasure::Failure { stdout, stderr, .. } => {
+                self.print_diff(result.name(), stdout, stderr)
+            }
+            TestMeasure::InternalProgramError { description } => {
+                let _ = writeln!(self.out, "         {description}");
+            }
+            TestMeasure::MemoryLimitExceeded { peak_memory } => {
+                let _ = writeln!(self.out, "         peak memory {} KiB", peak_memory / 1024);
+            }
+            TestMeasure::Skipped { reason } => {
+                let _ = writeln!(self.out, "         {reason}");
+            }
+            _ => {}
+        }
+
+        if outcome.is_failure() {
+            self.failed
+                .push((result.name().to_string(), outcome.verdict()));
+        }
+    }
+
+    /// Diff of the first output which differs from the expected one.
+    fn print_diff(&mut self, name: &str, stdout: &str, stderr: &str) {
+        let Some(expected) = &self.expected else {
+            return;
+        };
+
+        let read = |dir: &Path, extension: &str| {
+            std::fs::read_to_string(dir.join(format!("{name}.{extension}"))).unwrap_or_default()
+        };
+        let expected_stdout = read(&expected.out_dir, "out");
+
+        let (expected, actual, stream) = if expected_stdout != stdout {
+            (expected_stdout, stdout, "stdout")
+        } else {
+            (read(&expected.err_dir, "err"), stderr, "stderr")
+        };
+
+        let diff = similar::TextDiff::from_lines(expected.as_str(), actual);
+        let diff = diff
+            .unified_diff()
+            .context_radius(1)
+            .header(&format!("expected {stream}"), &format!("actual {stream}"))
+            .to_string();
+
+        let lines: Vec<&str> = diff.lines().collect();
+        for line in lines.iter().take(DIFF_LINES) {
+            let line = match line.chars().next() {
+                Some('-') => self.paint(Color::Red, line),
+                Some('+') => self.paint(Color::Green, line),
+                Some('@') => self.paint(Color::Dim, line),
+                _ => line.to_string(),
+            };
+            let _ = writeln!(self.out, "         {line}");
+        }
+        if lines.len() > DIFF_LINES {
+            let _ = writeln!(
+                self.out,
+                "         ... {} more lines",
+                lines.len() - DIFF_LINES
+            );
+        }
+    }
+
+    fn summary(&mut self, summary: &RunSummary) {
+        let rows = [
+            ("passed", summary.passed().to_string(), Color::Green),
+            ("failed", summary.failed().to_string(), Color::Red),
+            ("not run", summary.not_run().to_string(), Color::Yellow),
+            ("excluded", summary.excluded().len().to_string(), Color::Dim),
+            (
+                "time",
+                format!("{:.2}s", self.started.elapsed().as_secs_f64()),
+                Color::Dim,
+            ),
+        ];
+
+        let _ = writeln!(self.out);
+        for (label, value, color) in rows {
+            let value = self.paint(color, &format!("{value:>8}"));
+            let _ = writeln!(self.out, "  {label:<10}{value}");
+        }
+
+        if !self.failed.is_empty() {
+            let _ = writeln!(self.out, "\nFailed tests:");
+            for (name, verdict) in &self.failed {
+                let _ = writeln!(self.out, "  {name:<24} {verdict}");
+            }
+        }
+    }
+
+    fn comparison(&mut self, comparison: &BaselineComparison) {
+        let regressions: Vec<_> = comparison.regressions().collect();
+        let _ = writeln!(
+            self.out,
+            "\nCompared with baseline: {} tests, {} regressed",
+            comparison.tests.len(),
+            regressions.len()
+        );
+
+        for test in regressions {
+            let percent = test
+                .time_delta_percent()
+                .map(|percent| format!(" ({percent:+.1}%)"))
+                .unwrap_or_default();
+            let line = format!(
+                "  {:<24} {:.3}s -> {:.3}s{percent}",
+                test.name, test.baseline.time, test.current.time
+            );
+            let _ = writeln!(self.out, "{}", self.paint(Color::Red, &line));
+        }
+    }
+
+    fn input_minimized(
+        &mut self,
+        name: &str,
+        input: &[u8],
+        original_size: u64,
+        measure: &TestMeasure,
+    ) {
+        let _ = writeln!(
+            self.out,
+            "Minimized input of {name}: {} bytes instead of {original_size}, still {}",
+            input.len(),
+            self.paint(Color::Red, measure.verdict())
+        );
+
+        // Only the printed lines are decoded, the input itself may not be text.
+        let text = String::from_utf8_lossy(input);
+        let lines: Vec<_> = text.lines().collect();
+        for line in lines.iter().take(DIFF_LINES) {
+            let _ = writeln!(self.out, "  {line}");
+        }
+        if lines.len() > DIFF_LINES {
+            let more = format!("  ... {} more lines", lines.len() - DIFF_LINES);
+            let _ = writeln!(self.out, "{}", self.paint(Color::Dim, &more));
+        }
+    }
+}
+
+impl<W: Write> Output for PrettyOutput<W> {
+    fn emit(&mut self, message: BackendMessage) {
+        match message {
+            BackendMessage::Hello { trster_version, .. } => {
+                let header = format!("trster {trster_version}");
+                let _ = writeln!(self.out, "{}", self.paint(Color::Dim, &header));
+            }
+            // Tests run in parallel, only finished ones get a line.
+            BackendMessage::ExecutionStarted { .. } => {}
+            BackendMessage::TestCompleted { result } => self.test_completed(&result),
+            BackendMessage::Summary { summary } => self.summary(&summary),
+            BackendMessage::BaselineComparison { comparison } => self.comparison(&comparison),
+            BackendMessage::InputMinimized {
+                test_name,
+                input,
+                original_size,
+                measure,
+            } => self.input_minimized(&test_name, &input, original_size, &measure),
+            BackendMessage::ConfigRejected { description, .. } => {
+                let _ = writeln!(self.out, "{}", self.paint(Color::Red, &description));
+            }
+            BackendMessage::TestingProcessCompleted => {}
+        }
+
+        let _ = self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_test_with_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("sum.out"), "1\n2\n3\n").unwrap();
+
+        let expected = ExpectedOutputs {
+            out_dir: dir.path().into(),
+            err_dir: dir.path().into(),
+        };
+        let mut output = PrettyOutput::new(Vec::new(), false, Some(expected));
+
+        let results = [
+            TestResult::new(
+                "ok".into(),
+                TestMeasure::Success {
+                    time: 0.5,
+                    exit_status: Some(0),
+                },
+            ),
+            TestResult::new(
+                "sum".into(),
+                TestMeasure::Failure {
+                    stdout: "1\n5\n3\n".into(),
+                    stderr: String::new(),
+                    exit_status: Some(0),
+                },
+            ),
+        ];
+        for result in &results {
+            output.emit(BackendMessage::TestCompleted {
+                result: result.clone(),
+            });
+        }
+        output.emit(BackendMessage::Summary {
+            summary: RunSummary::new(&results, Vec::new()),
+        });
+
+        let text = String::from_utf8(output.out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "  PASS ok 0.500s");
+        assert_eq!(lines[1], "  FAIL sum");
+        assert!(lines.contains(&"         -2"));
+        assert!(lines.contains(&"         +5"));
+        assert!(lines.contains(&"  failed           1"));
+        assert_eq!(
+            lines.last(),
+            Some(&"  sum                      wrong answer")
+        );
+    }
+}
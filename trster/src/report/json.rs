@@ -0,0 +1,97 @@
+use serde::Serialize;
+use trst_types::{RunSummary, TestMeasure, TestResult, Timing};
+
+use super::truncate;
+
+#[derive(Serialize)]
+struct Report<'a> {
+    passed: u64,
+    failed: u64,
+    not_run: u64,
+    tests: Vec<TestReport<'a>>,
+}
+
+#[derive(Serialize)]
+struct TestReport<'a> {
+    name: &'a str,
+    verdict: &'static str,
+    /// Median wall time in seconds.
+    time: Option<f64>,
+    timing: Option<&'a Timing>,
+    flaky: bool,
+    exit_status: Option<i32>,
+    /// Details of the verdict, such as the skip reason or the peak memory.
+    message: Option<String>,
+    stdout: Option<String>,
+    stderr: Option<String>,
+}
+
+impl<'a> TestReport<'a> {
+    fn new(result: &'a TestResult) -> Self {
+        let mut report = Self {
+            name: result.name(),
+            verdict: result.outcome().verdict(),
+            time: result.time(),
+            timing: result.timing(),
+            flaky: result.is_flaky(),
+            exit_status: None,
+            message: None,
+            stdout: None,
+            stderr: None,
+        };
+
+        match result.outcome() {
+            TestMeasure::Success { exit_status, .. } => report.exit_status = *exit_status,
+            TestMeasure::Failure {
+                stdout,
+                stderr,
+                exit_status,
+            } => {
+                report.exit_status = *exit_status;
+                report.stdout = Some(truncate(stdout));
+                report.stderr = Some(truncate(stderr));
+            }
+            TestMeasure::InternalProgramError { description } => {
+                report.message = Some(description.clone())
+            }
+            TestMeasure::MemoryLimitExceeded { peak_memory } => {
+                report.message = Some(format!("peak memory {peak_memory} bytes"))
+            }
+            TestMeasure::Skipped { reason } => report.message = Some(reason.clone()),
+            TestMeasure::Timeout | TestMeasure::OutputLimitExceeded | TestMeasure::Cancelled => {}
+        }
+
+        report
+    }
+}
+
+pub fn render(results: &[TestResult]) -> String {
+    let summary = RunSummary::new(results, Vec::new());
+    let report = Report {
+        passed: summary.passed(),
+        failed: summary.failed(),
+        not_run: summary.not_run(),
+        tests: results.iter().map(TestReport::new).collect(),
+    };
+
+    serde_json::to_string_pretty(&report).expect("report contains only serializable data")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let report: serde_json::Value =
+            serde_json::from_str(&render(&super::super::tests::results())).unwrap();
+
+        assert_eq!(report["passed"], 1);
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["not_run"], 1);
+        assert_eq!(report["tests"][0]["time"], 0.25);
+        assert_eq!(report["tests"][1]["verdict"], "wrong answer");
+        assert_eq!(report["tests"][1]["stdout"], "42 & more\n");
+        assert_eq!(report["tests"][2]["message"], "fail-fast");
+    }
+}
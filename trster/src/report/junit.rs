@@ -0,0 +1,134 @@
+use std::fmt::Write;
+
+use trst_types::{TestMeasure, TestResult};
+
+use super::truncate;
+
+const SUITE_NAME: &str = "trst";
+
+pub fn render(results: &[TestResult]) -> String {
+    let count = |predicate: fn(&TestMeasure) -> bool| {
+        results
+            .iter()
+            .filter(|result| predicate(result.outcome()))
+            .count()
+    };
+
+    let errors = count(|outcome| matches!(outcome, TestMeasure::InternalProgramError { .. }));
+    let failures = count(TestMeasure::is_failure) - errors;
+    let skipped = count(|outcome| {
+        matches!(
+            outcome,
+            TestMeasure::Skipped { .. } | TestMeasure::Cancelled
+        )
+    });
+    let time: f64 = results.iter().filter_map(TestResult::time).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let attributes = format!(
+        "name=\"{SUITE_NAME}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" skipped=\"{skipped}\" time=\"{time:.3}\"",
+        results.len()
+    );
+    let _ = writeln!(xml, "<testsuites {attributes}>");
+    let _ = writeln!(xml, "  <testsuite {attributes}>");
+
+    for result in results {
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{SUITE_NAME}\" time=\"{:.3}\"",
+            escape(result.name()),
+            result.time().unwrap_or_default()
+        );
+
+        let verdict = result.outcome().verdict();
+        let body = match result.outcome() {
+            TestMeasure::Success { .. } => None,
+            TestMeasure::Failure {
+                stdout,
+                stderr,
+                exit_status,
+            } => {
+                let message = match exit_status {
+                    Some(code) => format!("{verdict}, exit status {code}"),
+                    None => format!("{verdict}, killed by a signal"),
+                };
+
+                Some(format!(
+                    "      <failure message=\"{}\" type=\"Failure\"/>\n      <system-out>{}</system-out>\n      <system-err>{}</system-err>\n",
+                    escape(&message),
+                    escape(&truncate(stdout)),
+                    escape(&truncate(stderr))
+                ))
+            }
+            TestMeasure::InternalProgramError { description } => Some(format!(
+                "      <error message=\"{verdict}\" type=\"InternalProgramError\">{}</error>\n",
+                escape(description)
+            )),
+            TestMeasure::Timeout => Some(format!(
+                "      <failure message=\"{verdict}\" type=\"Timeout\"/>\n"
+            )),
+            TestMeasure::MemoryLimitExceeded { peak_memory } => Some(format!(
+                "      <failure message=\"{verdict}, peak memory {peak_memory} bytes\" type=\"MemoryLimitExceeded\"/>\n"
+            )),
+            TestMeasure::OutputLimitExceeded => Some(format!(
+                "      <failure message=\"{verdict}\" type=\"OutputLimitExceeded\"/>\n"
+            )),
+            TestMeasure::Cancelled => Some(format!("      <skipped message=\"{verdict}\"/>\n")),
+            TestMeasure::Skipped { reason } => Some(format!(
+                "      <skipped message=\"{}\"/>\n",
+                escape(reason)
+            )),
+        };
+
+        match body {
+            Some(body) => {
+                let _ = write!(xml, ">\n{body}    </testcase>\n");
+            }
+            None => xml.push_str("/>\n"),
+        }
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Escapes text for use in attributes and element content. Control
+/// characters are not allowed in XML 1.0 at all and are replaced.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' | '\r' => escaped.push(character),
+            control if control.is_control() => escaped.push('\u{FFFD}'),
+            other => escaped.push(other),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let xml = render(&super::super::tests::results());
+
+        assert!(xml.contains(
+            "<testsuite name=\"trst\" tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\" time=\"0.250\">"
+        ));
+        assert!(xml.contains("<testcase name=\"sample\" classname=\"trst\" time=\"0.250\"/>"));
+        assert!(xml.contains("<testcase name=\"big&lt;1&gt;\""));
+        assert!(xml.contains("<failure message=\"wrong answer, exit status 1\" type=\"Failure\"/>"));
+        assert!(xml.contains("<system-out>42 &amp; more\n</system-out>"));
+        assert!(xml.contains("<skipped message=\"fail-fast\"/>"));
+        assert_eq!(escape("a\u{1}b"), "a\u{FFFD}b");
+    }
+}
@@ -0,0 +1,156 @@
+mod json;
+mod junit;
+mod tap;
+
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use trst_types::TestResult;
+
+/// Longest part of a program output included in a report, in bytes.
+const OUTPUT_LIMIT: usize = 4 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+    Tap,
+}
+
+impl ReportFormat {
+    pub fn render(self, results: &[TestResult]) -> String {
+        match self {
+            Self::Json => json::render(results),
+            Self::Junit => junit::render(results),
+            Self::Tap => tap::render(results),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "json" => Ok(Self::Json),
+            "junit" => Ok(Self::Junit),
+            "tap" => Ok(Self::Tap),
+            _ => Err(format!(
+                "unknown report format '{name}', expected json, junit or tap"
+            )),
+        }
+    }
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Json => "JSON",
+            Self::Junit => "JUnit XML",
+            Self::Tap => "TAP",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Report requested on the command line as `FORMAT=PATH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportTarget {
+    pub format: ReportFormat,
+    pub path: PathBuf,
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(argument: &str) -> Result<Self, Self::Err> {
+        let (format, path) = argument
+            .split_once('=')
+            .ok_or_else(|| format!("expected FORMAT=PATH, got '{argument}'"))?;
+
+        Ok(Self {
+            format: format.parse()?,
+            path: path.into(),
+        })
+    }
+}
+
+impl ReportTarget {
+    pub fn write(&self, results: &[TestResult]) -> std::io::Result<()> {
+        std::fs::write(&self.path, self.format.render(results))
+    }
+}
+
+/// Cuts program output to [`OUTPUT_LIMIT`] bytes, noting how much was left out.
+fn truncate(output: &str) -> String {
+    if output.len() <= OUTPUT_LIMIT {
+        return output.to_string();
+    }
+
+    let mut end = OUTPUT_LIMIT;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!(
+        "{}\n... ({} more bytes)",
+        &output[..end],
+        output.len() - end
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use trst_types::TestMeasure;
+
+    use super::*;
+
+    pub(super) fn results() -> Vec<TestResult> {
+        vec![
+            TestResult::new(
+                "sample".into(),
+                TestMeasure::Success {
+                    time: 0.25,
+                    exit_status: Some(0),
+                },
+            ),
+            TestResult::new(
+                "big<1>".into(),
+                TestMeasure::Failure {
+                    stdout: "42 & more\n".into(),
+                    stderr: String::new(),
+                    exit_status: Some(1),
+                },
+            ),
+            TestResult::new(
+                "slow".into(),
+                TestMeasure::Skipped {
+                    reason: "fail-fast".into(),
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(
+            "junit=out/report.xml".parse(),
+            Ok(ReportTarget {
+                format: ReportFormat::Junit,
+                path: "out/report.xml".into(),
+            })
+        );
+        assert!("xml=report.xml".parse::<ReportTarget>().is_err());
+        assert!("report.xml".parse::<ReportTarget>().is_err());
+    }
+
+    #[test]
+    fn truncation() {
+        assert_eq!(truncate("short"), "short");
+
+        let long = "ż".repeat(OUTPUT_LIMIT);
+        let truncated = truncate(&long);
+        assert!(truncated.starts_with(&long[..OUTPUT_LIMIT]));
+        assert!(truncated.ends_with(&format!("({} more bytes)", long.len() - OUTPUT_LIMIT)));
+    }
+}
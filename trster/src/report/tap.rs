@@ -0,0 +1,96 @@
+use std::fmt::Write;
+
+use trst_types::{TestMeasure, TestResult};
+
+use super::truncate;
+
+/// Renders TAP version 13, details of failures go to YAML blocks.
+pub fn render(results: &[TestResult]) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n", results.len());
+
+    for (index, result) in results.iter().enumerate() {
+        let number = index + 1;
+        // '#' starts a directive in TAP, so it cannot appear in the description.
+        let name = result.name().replace('#', "\\#");
+        let outcome = result.outcome();
+
+        match outcome {
+            TestMeasure::Skipped { reason } => {
+                let _ = writeln!(tap, "ok {number} - {name} # SKIP {reason}");
+                continue;
+            }
+            TestMeasure::Cancelled => {
+                let _ = writeln!(tap, "ok {number} - {name} # SKIP cancelled");
+                continue;
+            }
+            TestMeasure::Success { .. } => {
+                let _ = writeln!(tap, "ok {number} - {name}");
+            }
+            _ => {
+                let _ = writeln!(tap, "not ok {number} - {name}");
+            }
+        }
+
+        tap.push_str("  ---\n");
+        let _ = writeln!(tap, "  verdict: {}", outcome.verdict());
+        if let Some(time) = result.time() {
+            let _ = writeln!(tap, "  time: {time:.6}");
+        }
+        if result.is_flaky() {
+            tap.push_str("  flaky: true\n");
+        }
+
+        match outcome {
+            TestMeasure::Failure {
+                stdout,
+                stderr,
+                exit_status,
+            } => {
+                if let Some(code) = exit_status {
+                    let _ = writeln!(tap, "  exit_status: {code}");
+                }
+                write_block(&mut tap, "stdout", &truncate(stdout));
+                write_block(&mut tap, "stderr", &truncate(stderr));
+            }
+            TestMeasure::InternalProgramError { description } => {
+                write_block(&mut tap, "message", description)
+            }
+            TestMeasure::MemoryLimitExceeded { peak_memory } => {
+                let _ = writeln!(tap, "  peak_memory: {peak_memory}");
+            }
+            _ => {}
+        }
+
+        tap.push_str("  ...\n");
+    }
+
+    tap
+}
+
+/// Writes a YAML literal block keeping the text verbatim.
+fn write_block(tap: &mut String, key: &str, text: &str) {
+    let _ = writeln!(tap, "  {key}: |-");
+    for line in text.lines() {
+        let _ = writeln!(tap, "    {line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let tap = render(&super::super::tests::results());
+
+        assert_eq!(
+            tap,
+            "TAP version 13\n\
+             1..3\n\
+             ok 1 - sample\n  ---\n  verdict: passed\n  time: 0.250000\n  ...\n\
+             not ok 2 - big<1>\n  ---\n  verdict: wrong answer\n  exit_status: 1\n  \
+             stdout: |-\n    42 & more\n  stderr: |-\n  ...\n\
+             ok 3 - slow # SKIP fail-fast\n"
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use std::{collections::HashSet, path::Path};
+
+use trst_types::TestResult;
+
+/// Writes results of the run as JSON, replacing the previous content.
+pub fn save(path: &Path, results: &[TestResult]) -> std::io::Result<()> {
+    let content = serde_json::to_vec_pretty(results)?;
+    std::fs::write(path, content)
+}
+
+pub fn load(path: &Path) -> std::io::Result<Vec<TestResult>> {
+    let content = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+/// Names of the tests which failed in the saved run.
+pub fn load_failed(path: &Path) -> std::io::Result<HashSet<String>> {
+    Ok(load(path)?
+        .into_iter()
+        .filter(|result| result.outcome().is_failure())
+        .map(|result| result.name().to_string())
+        .collect())
+}
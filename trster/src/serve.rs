@@ -0,0 +1,639 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{Shutdown, TcpListener},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::UnixListener,
+    },
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use trst_types::{
+    encode_frame, BackendMessage, Config, FrameDecoder, RunningMode, SessionAddress,
+    SessionRequest, SessionResponse, SessionStream,
+};
+
+use crate::{
+    config,
+    testing::{self, Executor, Output},
+};
+
+const READ_CHUNK: usize = 8192;
+/// Time a client has to authenticate after connecting.
+const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections which did not authenticate yet, more are closed right away.
+const MAX_PENDING_CONNECTIONS: usize = 16;
+
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// TCP address such as 0.0.0.0:7070, or a Unix socket as unix:PATH.
+    /// TCP is not encrypted, the token and tests travel in plaintext
+    #[arg(long)]
+    listen: SessionAddress,
+
+    /// File holding the token clients authenticate with
+    #[arg(long)]
+    token_file: PathBuf,
+
+    /// Directory the session directories are created in, the system one by default
+    #[arg(long)]
+    work_dir: Option<PathBuf>,
+}
+
+pub fn run(args: &ServeArgs) -> ExitCode {
+    let token = match std::fs::read_to_string(&args.token_file) {
+        Ok(token) if !token.trim().is_empty() => Arc::new(token.trim().to_string()),
+        Ok(_) => {
+            eprintln!("error: token file '{}' is empty", args.token_file.display());
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!(
+                "error: cannot read token file '{}': {e}",
+                args.token_file.display()
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    let work_dir = Arc::new(args.work_dir.clone().unwrap_or_else(std::env::temp_dir));
+    let pending = PendingConnections::default();
+
+    let accept = |stream: std::io::Result<SessionStream>| {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::error!("Cannot accept a connection. Error = {e}");
+                return;
+            }
+        };
+        let Some(guard) = pending.enter() else {
+            log::warn!("Too many connections are not authenticated yet, closing a new one");
+            return;
+        };
+
+        let (token, work_dir) = (token.clone(), work_dir.clone());
+        std::thread::spawn(move || {
+            if let Err(e) = serve_connection(stream, &token, &work_dir, guard) {
+                log::error!("Session ended with an error. Error = {e}");
+            }
+        });
+    };
+
+    let listening = match &args.listen {
+        SessionAddress::Tcp(address) => TcpListener::bind(address).map(|listener| {
+            for stream in listener.incoming() {
+                accept(stream.map(SessionStream::Tcp));
+            }
+        }),
+        SessionAddress::Unix(path) => {
+            // A socket left behind by a previous server would make binding fail.
+            if std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+                let _ = std::fs::remove_file(path);
+            }
+            UnixListener::bind(path).map(|listener| {
+                for stream in listener.incoming() {
+                    accept(stream.map(SessionStream::Unix));
+                }
+            })
+        }
+    };
+
+    match listening {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: cannot listen on {:?}: {e}", args.listen);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Counter of the connections which did not authenticate yet, so that clients
+/// which never send a token cannot take up threads of the server without end.
+#[derive(Clone, Default)]
+pub(crate) struct PendingConnections(Arc<AtomicUsize>);
+
+impl PendingConnections {
+    /// Counts a new connection until the guard is dropped, `None` once the cap is reached.
+    pub(crate) fn enter(&self) -> Option<PendingGuard> {
+        self.0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < MAX_PENDING_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()?;
+
+        Some(PendingGuard(self.0.clone()))
+    }
+}
+
+pub(crate) struct PendingGuard(Arc<AtomicUsize>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Route of the control frames to the running tests. The reader installs it
+/// as soon as it decodes a run request, so no frame sent after the request is
+/// lost, and the run takes the receiving end.
+#[derive(Default)]
+struct ControlRoute {
+    sender: Option<Sender<Vec<u8>>>,
+    frames: Option<Receiver<Vec<u8>>>,
+}
+
+type ControlSlot = Arc<Mutex<ControlRoute>>;
+
+/// Serves one client until it disconnects, in a session directory of its own.
+/// The connection counts as pending until it authenticates.
+pub(crate) fn serve_connection(
+    stream: SessionStream,
+    token: &str,
+    work_dir: &Path,
+    pending: PendingGuard,
+) -> std::io::Result<()> {
+    let mut writer = stream;
+    let mut decoder = FrameDecoder::<SessionRequest>::new();
+
+    match authenticate(&mut writer, &mut decoder, token) {
+        Ok(true) => respond(&mut writer, &SessionResponse::Authenticated)?,
+        authenticated => {
+            if let Ok(false) = authenticated {
+                let _ = respond(
+                    &mut writer,
+                    &SessionResponse::Error {
+                        description: "authentication failed".into(),
+                    },
+                );
+            }
+            let _ = writer.shutdown(Shutdown::Both);
+
+            return match authenticated {
+                Err(e) if e.kind() != ErrorKind::UnexpectedEof => Err(e),
+                _ => Ok(()),
+            };
+        }
+    }
+    drop(pending);
+
+    let control = ControlSlot::default();
+    let requests = spawn_reader(writer.try_clone()?, decoder, control.clone());
+
+    let dir = tempfile::Builder::new()
+        .prefix("trster-session-")
+        .tempdir_in(work_dir)?;
+    log::info!("Session opened in {}", dir.path().display());
+
+    for request in requests {
+        let response = match request {
+            SessionRequest::Authenticate { .. } => Err("already authenticated".to_string()),
+            SessionRequest::Upload {
+                path,
+                content,
+                executable,
+            } => upload(dir.path(), &path, &content, executable)
+                .map(|()| SessionResponse::Uploaded { path }),
+            SessionRequest::Compile {
+                language,
+                source,
+                binary,
+                flags,
+            } => session_path(dir.path(), &source).and_then(|source| {
+                let binary = session_path(dir.path(), &binary)?;
+                let output = std::process::Command::new(language.compiler())
+                    .args(flags)
+                    .arg("-o")
+                    .arg(binary)
+                    .arg(source)
+                    .current_dir(dir.path())
+                    .output()
+                    .map_err(|e| format!("cannot run {}: {e}", language.compiler()))?;
+
+                let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+                log.push_str(&String::from_utf8_lossy(&output.stderr));
+                Ok(SessionResponse::Compiled {
+                    success: output.status.success(),
+                    log,
+                })
+            }),
+            SessionRequest::Run { config } => match session_config(dir.path(), *config) {
+                Ok(config) => {
+                    run_tests(&config, &control, &mut writer);
+                    continue;
+                }
+                Err(e) => Err(e),
+            },
+            // Reader threads route control frames to the running tests.
+            SessionRequest::Control(_) => continue,
+        };
+
+        let response =
+            response.unwrap_or_else(|description| SessionResponse::Error { description });
+        respond(&mut writer, &response)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the first request within [`AUTHENTICATION_TIMEOUT`] and checks that
+/// it carries the token. Requests sent right after it stay in `decoder`.
+fn authenticate(
+    stream: &mut SessionStream,
+    decoder: &mut FrameDecoder<SessionRequest>,
+    token: &str,
+) -> std::io::Result<bool> {
+    let deadline = Instant::now() + AUTHENTICATION_TIMEOUT;
+    let mut chunk = [0; READ_CHUNK];
+
+    let authenticated = loop {
+        match decoder.next_message() {
+            Some(Ok(SessionRequest::Authenticate { token: offered })) => {
+                break tokens_match(&offered, token)
+            }
+            Some(_) => break false,
+            None => {}
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(std::io::Error::new(
+                ErrorKind::TimedOut,
+                "the client did not authenticate in time",
+            ));
+        }
+        stream.set_read_timeout(Some(remaining))?;
+
+        let read = match stream.read(&mut chunk) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(read) => read,
+            // Timeouts are reported once the deadline is checked again.
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
+        };
+        decoder.push(&chunk[..read]);
+    };
+
+    stream.set_read_timeout(None)?;
+    Ok(authenticated)
+}
+
+/// Compares tokens in a time which depends only on their lengths, so that it
+/// does not tell how much of a guess was right.
+fn tokens_match(offered: &str, token: &str) -> bool {
+    let difference = offered
+        .bytes()
+        .zip(token.bytes())
+        .fold(0, |difference, (offered, token)| {
+            difference | (offered ^ token)
+        });
+
+    offered.len() == token.len() && std::hint::black_box(difference) == 0
+}
+
+/// Decodes requests of the client, control frames go straight to the running
+/// tests. Requests already in `decoder` come first.
+fn spawn_reader(
+    mut reader: SessionStream,
+    mut decoder: FrameDecoder<SessionRequest>,
+    control: ControlSlot,
+) -> Receiver<SessionRequest> {
+    let (sender, requests) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut chunk = [0; READ_CHUNK];
+
+        loop {
+            while let Some(request) = decoder.next_message() {
+                match request {
+                    Ok(SessionRequest::Control(message)) => {
+                        if let Some(sender) = &control.lock().unwrap().sender {
+                            let _ = sender.send(encode_frame(&message));
+                        }
+                    }
+                    Ok(request) => {
+                        if let SessionRequest::Run { .. } = request {
+                            let (sender, frames) = mpsc::channel();
+                            *control.lock().unwrap() = ControlRoute {
+                                sender: Some(sender),
+                                frames: Some(frames),
+                            };
+                        }
+                        if sender.send(request).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => log::error!("Invalid session request. Error = {e}"),
+                }
+            }
+
+            if decoder.is_failed() {
+                break;
+            }
+
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::error!("Error while reading a session. Error = {e}");
+                    break;
+                }
+            };
+            decoder.push(&chunk[..read]);
+        }
+    });
+
+    requests
+}
+
+fn respond<W: Write>(writer: &mut W, response: &SessionResponse) -> std::io::Result<()> {
+    writer.write_all(&encode_frame(response))?;
+    writer.flush()
+}
+
+/// Path inside the session directory, anything which could leave it is refused.
+fn session_path(dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    let inside = Path::new(relative)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if relative.is_empty() || !inside {
+        return Err(format!("'{relative}' is not a path inside the session"));
+    }
+
+    Ok(dir.join(relative))
+}
+
+fn upload(dir: &Path, path: &str, content: &[u8], executable: bool) -> Result<(), String> {
+    let path = session_path(dir, path)?;
+    let write = || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+        if executable {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        Ok::<_, std::io::Error>(())
+    };
+
+    write().map_err(|e| format!("cannot write '{}': {e}", path.display()))
+}
+
+/// Configuration with its paths resolved in the session directory.
+fn session_config(dir: &Path, config: Config) -> Result<Config, String> {
+    let RunningMode::Local {
+        in_test_path,
+        out_test_path,
+        err_test_path,
+        compiled_program_path,
+    } = config.running_mode()
+    else {
+        return Err(format!(
+            "{} is not supported by trster serve",
+            config.running_mode()
+        ));
+    };
+
+    let resolve =
+        |path: &str| session_path(dir, path).map(|path| path.to_string_lossy().into_owned());
+    let mode = RunningMode::Local {
+        in_test_path: resolve(in_test_path)?,
+        out_test_path: resolve(out_test_path)?,
+        err_test_path: resolve(err_test_path)?,
+        compiled_program_path: resolve(compiled_program_path)?,
+    };
+
+    let config = config.with_running_mode(mode);
+    if let RunningMode::Local {
+        in_test_path,
+        out_test_path,
+        err_test_path,
+        ..
+    } = config.running_mode()
+    {
+        // Tests without expected error outputs still need the directories.
+        for dir in [in_test_path, out_test_path, err_test_path] {
+            std::fs::create_dir_all(dir).map_err(|e| format!("cannot create '{dir}': {e}"))?;
+        }
+    }
+    config::validate(&config).map_err(|e| e.to_string())?;
+
+    Ok(config)
+}
+
+/// Frames of a run wrapped into session responses.
+struct SessionOutput<'a, W: Write> {
+    writer: &'a mut W,
+}
+
+impl<W: Write> Output for SessionOutput<'_, W> {
+    fn emit(&mut self, message: BackendMessage) {
+        // A client which went away is noticed by the reader thread.
+        let _ = respond(self.writer, &SessionResponse::Backend(message));
+    }
+}
+
+/// Control frames forwarded by the reader thread, the input ends with the run.
+struct ControlReader {
+    frames: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl Read for ControlReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.frames.recv() {
+                Ok(frame) => self.pending = frame,
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let read = buf.len().min(self.pending.len());
+        buf[..read].copy_from_slice(&self.pending[..read]);
+        self.pending.drain(..read);
+        Ok(read)
+    }
+}
+
+fn run_tests<W: Write>(config: &Config, control: &ControlSlot, writer: &mut W) {
+    let frames = control.lock().unwrap().frames.take().unwrap_or_else(|| {
+        let (_, frames) = mpsc::channel();
+        frames
+    });
+
+    let executor = testing::load_tests(config, None);
+    let control_reader = ControlReader {
+        frames,
+        pending: Vec::new(),
+    };
+    executor.execute_testing(config, None, control_reader, &mut SessionOutput { writer });
+
+    // Ending the control input of this run, unless the route of a newer one was installed.
+    let mut route = control.lock().unwrap();
+    if route.frames.is_none() {
+        route.sender = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use trst_types::{Concurrency, Language, SessionClient, TestMeasure};
+
+    use super::*;
+
+    fn serve(token: &'static str) -> (UnixStream, tempfile::TempDir) {
+        let work_dir = tempfile::tempdir().unwrap();
+        let (client, server) = UnixStream::pair().unwrap();
+
+        let dir = work_dir.path().to_path_buf();
+        let guard = PendingConnections::default().enter().unwrap();
+        std::thread::spawn(move || {
+            serve_connection(SessionStream::Unix(server), token, &dir, guard)
+        });
+
+        (client, work_dir)
+    }
+
+    #[test]
+    fn rejects_wrong_token() {
+        let (client, _work_dir) = serve("secret");
+        let mut probe = client.try_clone().unwrap();
+
+        let error = SessionClient::new(client, "guess").err().unwrap();
+        assert_eq!(error.to_string(), "authentication failed");
+        // The connection is closed after the error.
+        assert_eq!(probe.read(&mut [0; 16]).unwrap(), 0);
+
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret!", "secret"));
+    }
+
+    #[test]
+    fn caps_pending_connections() {
+        let pending = PendingConnections::default();
+        let mut guards: Vec<_> = (0..MAX_PENDING_CONNECTIONS)
+            .map(|_| pending.enter().unwrap())
+            .collect();
+        assert!(pending.enter().is_none());
+
+        guards.pop();
+        assert!(pending.enter().is_some());
+    }
+
+    #[test]
+    fn session_compiles_source() {
+        let (client, work_dir) = serve("secret");
+        let mut client = SessionClient::new(client, "secret").unwrap();
+
+        let source = work_dir.path().join("double.cpp");
+        std::fs::write(
+            &source,
+            "#include <iostream>\nint main() { int n; std::cin >> n; std::cout << n * 2 << std::endl; }\n",
+        )
+        .unwrap();
+        let tests = work_dir.path().join("tests");
+        std::fs::create_dir(&tests).unwrap();
+        std::fs::write(tests.join("a.in"), "2\n").unwrap();
+        std::fs::write(tests.join("a.out"), "4\n").unwrap();
+        std::fs::write(tests.join("a.err"), "").unwrap();
+
+        let tests = tests.to_string_lossy().into_owned();
+        let config = Config::new(
+            RunningMode::Local {
+                in_test_path: tests.clone(),
+                out_test_path: tests.clone(),
+                err_test_path: tests,
+                compiled_program_path: source.to_string_lossy().into_owned(),
+            },
+            vec!["a".into()],
+            Language::Cpp,
+            Concurrency::Disabled,
+        );
+
+        let (remote, _) = client
+            .upload_source(&config, &source, Language::Cpp, Vec::new())
+            .unwrap();
+        let mut outcomes = Vec::new();
+        client
+            .run(remote, |message| {
+                if let BackendMessage::TestCompleted { result } = message {
+                    outcomes.push(result.outcome().clone());
+                }
+            })
+            .unwrap();
+        assert!(matches!(outcomes[..], [TestMeasure::Success { .. }]));
+
+        std::fs::write(&source, "int main() { return }\n").unwrap();
+        let error = client
+            .upload_source(&config, &source, Language::Cpp, Vec::new())
+            .unwrap_err();
+        assert!(
+            error.to_string().starts_with("compilation failed"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn session_runs_tests_repeatedly() {
+        let (client, work_dir) = serve("secret");
+        let mut client = SessionClient::new(client, "secret").unwrap();
+
+        client
+            .upload(
+                "program",
+                b"#!/bin/sh\nread n; echo $((n * 2))\n".to_vec(),
+                true,
+            )
+            .unwrap();
+        client.upload("in/a.in", b"2\n".to_vec(), false).unwrap();
+        client.upload("out/a.out", b"4\n".to_vec(), false).unwrap();
+        client.upload("err/a.err", Vec::new(), false).unwrap();
+        assert!(client.upload("../escape", Vec::new(), false).is_err());
+
+        let config = Config::new(
+            RunningMode::Local {
+                in_test_path: "in".into(),
+                out_test_path: "out".into(),
+                err_test_path: "err".into(),
+                compiled_program_path: "./program".into(),
+            },
+            vec!["a".into()],
+            Language::Cpp,
+            Concurrency::Disabled,
+        );
+
+        for _ in 0..2 {
+            let mut outcomes = Vec::new();
+            client
+                .run(config.clone(), |message| {
+                    if let BackendMessage::TestCompleted { result } = message {
+                        outcomes.push(result.outcome().clone());
+                    }
+                })
+                .unwrap();
+
+            assert_eq!(outcomes.len(), 1);
+            assert!(matches!(outcomes[0], TestMeasure::Success { .. }));
+        }
+
+        let session_dirs = std::fs::read_dir(work_dir.path()).unwrap().count();
+        assert_eq!(session_dirs, 1);
+    }
+}
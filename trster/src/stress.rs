@@ -0,0 +1,341 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use trst_types::{Config, Limits, RunningMode, TestMeasure};
+
+use crate::{
+    config,
+    testing::{self, ProcessRun, Termination},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct StressArgs {
+    /// Configuration in RON format, its program is tested and the counterexample
+    /// is saved to its test directories
+    #[arg(long, short, required_unless_present = "config_file")]
+    configuration: Option<String>,
+
+    /// Path to configuration file in RON, TOML or JSON format
+    #[arg(long, conflicts_with = "configuration")]
+    config_file: Option<PathBuf>,
+
+    /// Program printing an input for the seed it gets as its only argument
+    #[arg(long)]
+    generator: String,
+
+    /// Trusted, possibly slow, solution whose outputs are expected
+    #[arg(long)]
+    reference: String,
+
+    /// Seed of the first input
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Number of inputs tried before giving up, unlimited by default
+    #[arg(long)]
+    iterations: Option<u64>,
+
+    /// Name of the saved test, stress-SEED by default
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Time limit of the generator and the reference in seconds
+    #[arg(long, default_value_t = 10.0)]
+    trusted_time_limit: f64,
+}
+
+/// Programs and seeds of a stress run, the tested program comes from the configuration.
+#[derive(Clone, Debug)]
+pub struct Stress {
+    pub generator: String,
+    pub reference: String,
+    pub first_seed: u64,
+    pub iterations: Option<u64>,
+    pub name: Option<String>,
+    /// Limits of the generator and the reference.
+    pub trusted_limits: Limits,
+}
+
+/// Input on which the tested program disagreed with the reference, saved as a test.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counterexample {
+    pub seed: u64,
+    pub name: String,
+    pub measure: TestMeasure,
+}
+
+pub fn run(args: &StressArgs) -> ExitCode {
+    let config = match &args.config_file {
+        Some(path) => config::from_file(path),
+        None => config::from_inline(args.configuration.as_ref().unwrap()),
+    };
+    let config = match config.and_then(|config| config::validate(&config).map(|_| config)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let stress = Stress {
+        generator: args.generator.clone(),
+        reference: args.reference.clone(),
+        first_seed: args.seed,
+        iterations: args.iterations,
+        name: args.name.clone(),
+        trusted_limits: Limits::new(args.trusted_time_limit, None, None),
+    };
+
+    match find_counterexample(&config, &stress, |seed| log::info!("Seed {seed} passed")) {
+        Ok(Some(counterexample)) => {
+            println!(
+                "seed {}: {}, saved as test {}",
+                counterexample.seed,
+                counterexample.measure.verdict(),
+                counterexample.name
+            );
+            ExitCode::FAILURE
+        }
+        Ok(None) => {
+            println!("no counterexample found");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Tries inputs of consecutive seeds until the tested program disagrees with
+/// the reference, then saves that input with the outputs of the reference as
+/// a new test. `on_passed` is called with every seed the outputs agreed on.
+pub fn find_counterexample(
+    config: &Config,
+    stress: &Stress,
+    mut on_passed: impl FnMut(u64),
+) -> Result<Option<Counterexample>, String> {
+    let RunningMode::Local {
+        in_test_path,
+        out_test_path,
+        err_test_path,
+        compiled_program_path,
+    } = config.running_mode()
+    else {
+        return Err("stress testing needs a local mode configuration".into());
+    };
+
+    let last_seed = match stress.iterations {
+        Some(iterations) => stress.first_seed.saturating_add(iterations),
+        None => u64::MAX,
+    };
+
+    for seed in stress.first_seed..last_seed {
+        let generated = run_trusted(
+            "generator",
+            &stress.generator,
+            &[seed.to_string()],
+            Vec::new(),
+            &stress.trusted_limits,
+            seed,
+        )?;
+        let input = generated.stdout;
+        let expected = run_trusted(
+            "reference",
+            &stress.reference,
+            &[],
+            input.clone(),
+            &stress.trusted_limits,
+            seed,
+        )?;
+
+        let name = stress
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("stress-{seed}"));
+        let tested = testing::run_program(
+            compiled_program_path,
+            &[],
+            input.clone(),
+            &config.limits_for(&name),
+        )
+        .map_err(|e| format!("cannot run the tested program: {e}"))?;
+
+        let measure = testing::judge(
+            &tested,
+            || Ok(String::from_utf8_lossy(&expected.stdout).into_owned()),
+            || Ok(String::from_utf8_lossy(&expected.stderr).into_owned()),
+        );
+        if !measure.is_failure() {
+            on_passed(seed);
+            continue;
+        }
+
+        // The checker also compares the error output, so the one of the reference is kept too.
+        save(
+            &name,
+            &[
+                (in_test_path, "in", input.as_slice()),
+                (out_test_path, "out", expected.stdout.as_slice()),
+                (err_test_path, "err", expected.stderr.as_slice()),
+            ],
+        )?;
+
+        return Ok(Some(Counterexample {
+            seed,
+            name,
+            measure,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Runs the generator or the reference, which must exit successfully.
+fn run_trusted(
+    role: &str,
+    program: &str,
+    args: &[String],
+    input: Vec<u8>,
+    limits: &Limits,
+    seed: u64,
+) -> Result<ProcessRun, String> {
+    let run = testing::run_program(program, args, input, limits)
+        .map_err(|e| format!("cannot run the {role} '{program}': {e}"))?;
+
+    match run.termination {
+        Termination::Exited(status) if status.success() => Ok(run),
+        Termination::Exited(status) => Err(format!("the {role} failed on seed {seed}: {status}")),
+        Termination::TimeLimitExceeded => {
+            Err(format!("the {role} exceeded its time limit on seed {seed}"))
+        }
+        termination => Err(format!(
+            "the {role} was stopped on seed {seed}: {termination:?}"
+        )),
+    }
+}
+
+/// Writes the files of the counterexample, an existing test is never
+/// overwritten and no half written test is left behind.
+fn save(name: &str, files: &[(&str, &str, &[u8])]) -> Result<(), String> {
+    let paths: Vec<_> = files
+        .iter()
+        .map(|(dir, extension, _)| Path::new(dir).join(format!("{name}.{extension}")))
+        .collect();
+    if let Some(path) = paths.iter().find(|path| path.exists()) {
+        return Err(format!("cannot save '{}': the test exists", path.display()));
+    }
+
+    for (index, (path, (_, _, content))) in paths.iter().zip(files).enumerate() {
+        let written = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(content));
+        if let Err(e) = written {
+            for written_path in &paths[..index] {
+                let _ = std::fs::remove_file(written_path);
+            }
+            return Err(format!("cannot save '{}': {e}", path.display()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{local_config, script};
+
+    /// Stress run of a program doubling its input which is wrong from 3 on.
+    fn setup(dir: &Path, reference: &str) -> (Config, Stress) {
+        let tested = script(
+            dir,
+            "tested",
+            "#!/bin/sh\nread n; if [ $n -ge 3 ]; then echo 0; else echo $((n * 2)); fi\n",
+        );
+        let config = local_config(dir.to_str().unwrap(), &tested, Vec::new());
+
+        let stress = Stress {
+            generator: script(dir, "generator", "#!/bin/sh\necho $1\n"),
+            reference: script(dir, "reference", reference),
+            first_seed: 1,
+            iterations: None,
+            name: None,
+            trusted_limits: Limits::new(5.0, None, None),
+        };
+
+        (config, stress)
+    }
+
+    #[test]
+    fn saves_first_counterexample() {
+        let dir = tempfile::tempdir().unwrap();
+        let (config, stress) = setup(dir.path(), "#!/bin/sh\nread n; echo $((n * 2))\n");
+
+        let mut passed = Vec::new();
+        let counterexample = find_counterexample(&config, &stress, |seed| passed.push(seed))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(passed, [1, 2]);
+        assert_eq!(counterexample.seed, 3);
+        assert_eq!(counterexample.name, "stress-3");
+        assert!(matches!(
+            counterexample.measure,
+            TestMeasure::Failure { ref stdout, .. } if stdout == "0\n"
+        ));
+        let read = |extension| std::fs::read_to_string(dir.path().join(extension)).unwrap();
+        assert_eq!(read("stress-3.in"), "3\n");
+        assert_eq!(read("stress-3.out"), "6\n");
+        assert_eq!(read("stress-3.err"), "");
+
+        // The saved test is never overwritten.
+        let error = find_counterexample(&config, &stress, |_| {}).unwrap_err();
+        assert!(error.contains("stress-3.in"), "{error}");
+    }
+
+    #[test]
+    fn stops_after_iterations() {
+        let dir = tempfile::tempdir().unwrap();
+        let (config, mut stress) = setup(dir.path(), "#!/bin/sh\nread n; echo $((n * 2))\n");
+        stress.iterations = Some(2);
+
+        assert_eq!(find_counterexample(&config, &stress, |_| {}), Ok(None));
+    }
+
+    #[test]
+    fn refuses_failing_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        let (config, stress) = setup(dir.path(), "#!/bin/sh\nexit 4\n");
+
+        let error = find_counterexample(&config, &stress, |_| {}).unwrap_err();
+        assert!(
+            error.starts_with("the reference failed on seed 1"),
+            "{error}"
+        );
+        assert!(!dir.path().join("stress-1.in").exists());
+    }
+
+    #[test]
+    fn keeps_test_with_same_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let (config, mut stress) = setup(dir.path(), "#!/bin/sh\nread n; echo $((n * 2))\n");
+        stress.name = Some("found".into());
+        std::fs::write(dir.path().join("found.err"), "kept").unwrap();
+
+        let error = find_counterexample(&config, &stress, |_| {}).unwrap_err();
+
+        assert!(error.contains("found.err"), "{error}");
+        assert!(!dir.path().join("found.in").exists());
+        assert!(!dir.path().join("found.out").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("found.err")).unwrap(),
+            "kept"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Fixtures shared by the tests of several modules.
+
+use std::{os::unix::fs::PermissionsExt, path::Path};
+
+use trst_types::{Concurrency, Config, Language, RunningMode, TestMeasure, TestResult};
+
+/// Passing result of the test `name` which took `time` seconds.
+pub fn passed(name: &str, time: f64) -> TestResult {
+    TestResult::new(
+        name.into(),
+        TestMeasure::Success {
+            time,
+            exit_status: Some(0),
+        },
+    )
+}
+
+/// Local mode configuration keeping the inputs and expected outputs of
+/// `test_list` in the same directory `tests`.
+pub fn local_config(tests: &str, program: &str, test_list: Vec<String>) -> Config {
+    Config::new(
+        RunningMode::Local {
+            in_test_path: tests.into(),
+            out_test_path: tests.into(),
+            err_test_path: tests.into(),
+            compiled_program_path: program.into(),
+        },
+        test_list,
+        Language::Cpp,
+        Concurrency::Disabled,
+    )
+}
+
+/// Writes an executable script and returns its path.
+pub fn script(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.to_str().unwrap().into()
+}
@@ -0,0 +1,108 @@
+use std::{os::unix::process::CommandExt, process::Command};
+
+/// Cores the trster process is allowed to run on, in ascending order.
+pub fn allowed_cores() -> Vec<usize> {
+    // SAFETY: cpu_set_t is a plain bit mask for which all zeroes is a valid value.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+
+    // SAFETY: the pointer and size describe the set declared above.
+    let result = unsafe { libc::sched_getaffinity(0, std::mem::size_of_val(&set), &mut set) };
+    if result != 0 {
+        log::warn!(
+            "Cannot read CPU affinity. Error = {}",
+            std::io::Error::last_os_error()
+        );
+        return Vec::new();
+    }
+
+    (0..libc::CPU_SETSIZE as usize)
+        // SAFETY: core index is below CPU_SETSIZE.
+        .filter(|core| unsafe { libc::CPU_ISSET(*core, &set) })
+        .collect()
+}
+
+/// Keeps a single logical CPU, the lowest numbered one, of every physical core.
+pub fn without_smt_siblings(cores: &[usize]) -> Vec<usize> {
+    cores
+        .iter()
+        .copied()
+        .filter(|core| {
+            let path = format!("/sys/devices/system/cpu/cpu{core}/topology/thread_siblings_list");
+
+            match std::fs::read_to_string(path) {
+                Ok(list) => parse_cpu_list(&list)
+                    .into_iter()
+                    .filter(|sibling| cores.contains(sibling))
+                    .min()
+                    .is_none_or(|first| first == *core),
+                Err(_) => true,
+            }
+        })
+        .collect()
+}
+
+/// Parses kernel CPU lists such as `0,4` or `0-3,8-11`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| {
+            let (first, last) = part.split_once('-').unwrap_or((part, part));
+            Some(first.parse::<usize>().ok()?..=last.parse::<usize>().ok()?)
+        })
+        .flatten()
+        .collect()
+}
+
+/// Restricts the program started by `command`, and every process it spawns,
+/// to a single core. Only the child is pinned, the threads of trster feeding
+/// and watching it keep every allowed core.
+pub fn pin_child(command: &mut Command, core: usize) {
+    // SAFETY: cpu_set_t is a plain bit mask for which all zeroes is a valid value.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+
+    // SAFETY: callers pass cores taken from allowed_cores, which are below CPU_SETSIZE.
+    unsafe { libc::CPU_SET(core, &mut set) };
+
+    // SAFETY: the closure only makes a syscall, which is safe between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_child_is_pinned() {
+        let cores = allowed_cores();
+        assert!(!cores.is_empty());
+
+        let core = *cores.last().unwrap();
+        let mut command = Command::new("grep");
+        command.args(["Cpus_allowed_list", "/proc/self/status"]);
+        pin_child(&mut command, core);
+        let output = command.output().unwrap();
+
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("Cpus_allowed_list:\t{core}\n")
+        );
+        assert_eq!(allowed_cores(), cores);
+    }
+
+    #[test]
+    fn cpu_lists() {
+        assert_eq!(parse_cpu_list("0,4\n"), vec![0, 4]);
+        assert_eq!(parse_cpu_list("0-2,8-9"), vec![0, 1, 2, 8, 9]);
+        assert_eq!(parse_cpu_list("3"), vec![3]);
+        assert_eq!(parse_cpu_list("x"), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,48 @@
+use std::io::Read;
+
+use trst_types::{FrameDecoder, FrontendMessage};
+
+const READ_CHUNK: usize = 1024;
+
+/// Passes every [`FrontendMessage`] frame read from `input` to `forward`,
+/// finishing with `None` once the input ends. Reading stops as soon as
+/// `forward` returns false.
+pub fn spawn_control_listener<R: Read + Send + 'static>(
+    mut input: R,
+    mut forward: impl FnMut(Option<FrontendMessage>) -> bool + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        let mut decoder = FrameDecoder::<FrontendMessage>::new();
+        let mut chunk = [0; READ_CHUNK];
+
+        loop {
+            let read = match input.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::error!("Error while reading control channel. Error = {e:#?}");
+                    break;
+                }
+            };
+
+            decoder.push(&chunk[..read]);
+            while let Some(message) = decoder.next_message() {
+                match message {
+                    Ok(message) => {
+                        if !forward(Some(message)) {
+                            return;
+                        }
+                    }
+                    Err(e) => log::error!("Invalid control message. Error = {e}"),
+                }
+            }
+
+            if decoder.is_failed() {
+                break;
+            }
+        }
+
+        forward(None);
+    });
+}
@@ -1,27 +1,49 @@
-use once_cell::sync::OnceCell;
-
-use trst_types::BackendMessage;
+use trst_types::{
+    BackendMessage, Baseline, FailurePolicy, FrontendMessage, Limits, Repetitions, TestMeasure,
+    TestResult,
+};
 
 use crate::{Concurrency, Config};
 
 use super::{
+    affinity, control,
+    pool::WorkerPool,
     test_unit::{TestingUnit, TestingUnitMessage},
-    Executor,
+    Executor, Output,
 };
 use std::{
-    io::Write,
-    sync::{mpsc::Sender, Arc},
+    collections::{HashMap, VecDeque},
+    io::Read,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
-static EXECUTOR: OnceCell<()> = OnceCell::new();
+#[derive(Debug)]
+pub enum ExecutorEvent {
+    Unit(TestingUnitMessage),
+    Control(FrontendMessage),
+    ControlClosed,
+}
+
+impl From<TestingUnitMessage> for ExecutorEvent {
+    fn from(msg: TestingUnitMessage) -> Self {
+        Self::Unit(msg)
+    }
+}
 
 #[derive(Debug)]
 pub struct TestExecutor {
     tests: Vec<TestingUnit>,
+    excluded: Vec<String>,
+    failure_policy: FailurePolicy,
 }
-#[derive(Debug)]
-pub enum TestExecutorError {
-    AlreadyCreated,
+
+/// Test handed over to the worker pool.
+struct RunningTest {
+    cancellation: Arc<AtomicBool>,
+    group: Option<String>,
 }
 
 #[derive(Clone)]
@@ -63,51 +85,97 @@ impl Paths {
             _ => todo!(),
         }
     }
+
+    /// Unit running the test `name` of these paths once.
+    pub fn unit(self, name: String, group: Option<String>, limits: Limits) -> TestingUnit {
+        TestingUnit::new(
+            self.program_path,
+            self.in_test_path,
+            self.out_test_path,
+            self.err_test_path,
+            name,
+            group,
+            limits,
+        )
+    }
 }
 
 impl TestExecutor {
-    pub fn new() -> Result<Self, TestExecutorError> {
-        if EXECUTOR.get().is_some() {
-            return Err(TestExecutorError::AlreadyCreated);
+    pub fn new(failure_policy: FailurePolicy) -> Self {
+        Self {
+            tests: Vec::new(),
+            excluded: Vec::new(),
+            failure_policy,
         }
+    }
 
-        Ok(Self { tests: Vec::new() })
+    /// Records a test of the configuration which will not be run.
+    pub fn exclude_test(&mut self, test_name: String) {
+        self.excluded.push(test_name);
     }
 
-    pub fn push_test(&mut self, paths: Paths, test_name: String) {
-        self.tests.push(TestingUnit::new(
-            paths.program_path,
-            paths.in_test_path,
-            paths.out_test_path,
-            paths.err_test_path,
-            test_name,
-        ))
+    pub fn push_test(
+        &mut self,
+        paths: Paths,
+        test_name: String,
+        group: Option<String>,
+        limits: Limits,
+        repetitions: Repetitions,
+    ) {
+        self.tests.push(
+            paths
+                .unit(test_name, group, limits)
+                .with_repetitions(repetitions),
+        )
     }
 
-    pub fn execute_concurrent(self, max_concurrent_testing: u64) {
-        let (tx, rx) = std::sync::mpsc::channel::<TestingUnitMessage>();
+    pub fn execute_concurrent<R: Read + Send + 'static, O: Output>(
+        self,
+        max_concurrent_testing: usize,
+        cores: Option<Vec<usize>>,
+        control: R,
+        output: &mut O,
+    ) -> Vec<TestResult> {
+        let (tx, rx) = std::sync::mpsc::channel::<ExecutorEvent>();
+        let control_events = tx.clone();
+        control::spawn_control_listener(control, move |message| {
+            let event = match message {
+                Some(message) => ExecutorEvent::Control(message),
+                None => ExecutorEvent::ControlClosed,
+            };
+            control_events.send(event).is_ok()
+        });
 
-        let thread_job = move |unit: TestingUnit, tx: Sender<TestingUnitMessage>| {
-            unit.run_test(tx);
-        };
+        let mut queue: VecDeque<TestingUnit> = self.tests.into();
+        let mut running: HashMap<String, RunningTest> = HashMap::new();
+        // Running tests stopped because of a failure, with the reason reported instead of cancellation.
+        let mut skipped: HashMap<String, String> = HashMap::new();
+        let mut pool = WorkerPool::new(0, cores, tx.clone());
+        let mut max_concurrent_testing = pool.ensure_workers(max_concurrent_testing.max(1));
+        let mut paused = false;
+        let mut results = Vec::new();
 
-        let mut number_of_tests = self.tests.len();
-        let mut tests = self.tests.into_iter();
+        loop {
+            while !paused && running.len() < max_concurrent_testing {
+                let Some(unit) = queue.pop_front() else {
+                    break;
+                };
 
-        for _ in 0..max_concurrent_testing {
-            if let Some(unit) = tests.next() {
-                let thread_tx = tx.clone();
+                running.insert(
+                    unit.name().to_string(),
+                    RunningTest {
+                        cancellation: unit.cancellation(),
+                        group: unit.group().map(str::to_string),
+                    },
+                );
+                pool.submit(unit);
+            }
 
-                std::thread::spawn(move || {
-                    thread_job(unit, thread_tx);
-                });
-            } else {
+            if running.is_empty() && queue.is_empty() {
                 break;
             }
-        }
 
-        while number_of_tests > 0 {
-            let msg = match rx.recv() {
+            let event = match rx.recv() {
                 Err(e) => {
                     log::error!("Error while receiving value from thread. Error = {e:#?}");
                     break;
@@ -115,46 +183,341 @@ impl TestExecutor {
                 Ok(val) => val,
             };
 
-            log::trace!("Message from thread = {msg:#?}");
+            log::trace!("Message from thread = {event:#?}");
 
-            match msg {
-                TestingUnitMessage::StartedExecution { .. } => {
-                    let print_message: BackendMessage = msg.into();
-
-                    let bytes = print_message.serialize();
-                    std::io::stdout().write_all(&bytes).unwrap();
+            match event {
+                ExecutorEvent::Unit(msg @ TestingUnitMessage::StartedExecution { .. }) => {
+                    output.emit(msg.into());
                 }
-                TestingUnitMessage::Done { .. } => {
-                    number_of_tests -= 1;
-                    let print_message: BackendMessage = msg.into();
+                ExecutorEvent::Unit(TestingUnitMessage::Done { result }) => {
+                    let group = running.remove(result.name()).and_then(|test| test.group);
 
-                    let bytes = print_message.serialize();
-                    std::io::stdout().write_all(&bytes).unwrap();
+                    let result = match skipped.remove(result.name()) {
+                        Some(reason) if *result.outcome() == TestMeasure::Cancelled => {
+                            TestResult::new(result.name().into(), TestMeasure::Skipped { reason })
+                        }
+                        _ => result,
+                    };
 
-                    if let Some(unit) = tests.next() {
-                        let thread_tx = tx.clone();
+                    let failed = result.outcome().is_failure();
+                    let failed_name = result.name().to_string();
+                    results.push(result.clone());
+                    output.emit(BackendMessage::TestCompleted { result });
 
-                        std::thread::spawn(move || {
-                            thread_job(unit, thread_tx);
-                        });
+                    if !failed {
+                        continue;
+                    }
+
+                    let (reason, group) = match (self.failure_policy, group) {
+                        (FailurePolicy::FailFast, _) => {
+                            (format!("fail-fast after test {failed_name} failed"), None)
+                        }
+                        (FailurePolicy::StopGroup, Some(group)) => (
+                            format!("test {failed_name} of group {group} failed"),
+                            Some(group),
+                        ),
+                        _ => continue,
+                    };
+                    let affected = |test_group: Option<&str>| {
+                        group.is_none() || test_group == group.as_deref()
+                    };
+
+                    for (name, test) in running.iter() {
+                        if affected(test.group.as_deref()) {
+                            test.cancellation.store(true, Ordering::Relaxed);
+                            skipped.insert(name.clone(), reason.clone());
+                        }
+                    }
+
+                    let (stopped, kept) = queue
+                        .drain(..)
+                        .partition::<VecDeque<_>, _>(|unit| affected(unit.group()));
+                    queue = kept;
+                    for unit in stopped {
+                        report(output, &mut results, unit.report_skipped(reason.clone()));
+                    }
+                }
+                ExecutorEvent::Control(FrontendMessage::Cancel) => {
+                    running
+                        .values()
+                        .for_each(|test| test.cancellation.store(true, Ordering::Relaxed));
+
+                    for unit in queue.drain(..) {
+                        report(output, &mut results, unit.report_cancelled());
+                    }
+                }
+                ExecutorEvent::Control(FrontendMessage::CancelTest { name }) => {
+                    if let Some(test) = running.get(&name) {
+                        test.cancellation.store(true, Ordering::Relaxed);
+                    } else if let Some(position) = queue.iter().position(|unit| unit.name() == name)
+                    {
+                        let unit = queue.remove(position).unwrap();
+                        report(output, &mut results, unit.report_cancelled());
                     }
                 }
+                ExecutorEvent::Control(FrontendMessage::Pause) => paused = true,
+                ExecutorEvent::Control(FrontendMessage::Resume) => paused = false,
+                ExecutorEvent::Control(FrontendMessage::SetConcurrency(threads)) => {
+                    max_concurrent_testing = pool.ensure_workers(threads.max(1) as usize);
+                }
+                // Nobody is left to resume the testing.
+                ExecutorEvent::ControlClosed => paused = false,
             }
         }
+
+        results
+    }
+}
+
+/// Cores the tests of the configuration may run on.
+pub fn usable_cores(config: &Config) -> Vec<usize> {
+    let cores = affinity::allowed_cores();
+    if config.cpu_options().skip_smt_siblings() {
+        affinity::without_smt_siblings(&cores)
+    } else {
+        cores
     }
 }
 
+/// Number of tests run at once when `usable_cores` cores are available.
+pub fn concurrent_tests(concurrency: &Concurrency, usable_cores: usize) -> usize {
+    match concurrency {
+        Concurrency::Disabled => 1,
+        Concurrency::Enabled(threads) => *threads as usize,
+        Concurrency::Auto { reserve } => usable_cores.saturating_sub(*reserve as usize),
+    }
+    .max(1)
+}
+
+/// Emits the result of a test which was not handed over to the pool.
+fn report<O: Output>(output: &mut O, results: &mut Vec<TestResult>, message: TestingUnitMessage) {
+    if let TestingUnitMessage::Done { result } = &message {
+        results.push(result.clone());
+    }
+
+    output.emit(message.into());
+}
+
 impl Executor for TestExecutor {
-    fn execute_testing(self, config: &Config) {
+    fn execute_testing<R: Read + Send + 'static, O: Output>(
+        self,
+        config: &Config,
+        baseline: Option<&Baseline>,
+        control: R,
+        output: &mut O,
+    ) -> Vec<TestResult> {
         log::trace!("Executing testing for {self:#?}\n With config {config:#?}");
 
-        match config.concurrency_settings() {
-            Concurrency::Disabled => self.execute_concurrent(1),
-            Concurrency::Enabled(threads) => self.execute_concurrent(*threads),
-        }
+        output.emit(super::hello());
+
+        let cores = usable_cores(config);
+        let threads = concurrent_tests(config.concurrency_settings(), cores.len());
+        let pinned_cores = config.cpu_options().pin_tests().then_some(cores);
+        let excluded = self.excluded.clone();
+        let results = self.execute_concurrent(threads, pinned_cores, control, output);
 
         log::trace!("Testing process done, emitting end message");
-        let end_message = BackendMessage::TestingProcessCompleted.serialize();
-        std::io::stdout().write_all(&end_message).unwrap();
+        super::finish_run(config, baseline, &results, excluded, output);
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::Path};
+
+    use trst_types::{FrameDecoder, TestMeasure};
+
+    use super::*;
+    use crate::test_support::script;
+
+    fn executor(dir: &Path, body: &str, tests: &[&str]) -> TestExecutor {
+        grouped_executor(dir, body, tests, &[], FailurePolicy::RunAll)
+    }
+
+    fn grouped_executor(
+        dir: &Path,
+        body: &str,
+        tests: &[&str],
+        group: &[&str],
+        failure_policy: FailurePolicy,
+    ) -> TestExecutor {
+        let program = script(dir, "program", &format!("#!/bin/sh\n{body}\n"));
+
+        let directory = Arc::new(dir.to_str().unwrap().to_string());
+        let paths = Paths {
+            program_path: Arc::new(program),
+            in_test_path: directory.clone(),
+            out_test_path: directory.clone(),
+            err_test_path: directory,
+        };
+
+        let mut executor = TestExecutor::new(failure_policy);
+        for name in tests {
+            std::fs::write(dir.join(format!("{name}.in")), name).unwrap();
+            for extension in ["out", "err"] {
+                std::fs::write(dir.join(format!("{name}.{extension}")), "").unwrap();
+            }
+
+            let group = group.contains(name).then(|| "group".to_string());
+            executor.push_test(
+                paths.clone(),
+                name.to_string(),
+                group,
+                Limits::default(),
+                Repetitions::default(),
+            );
+        }
+
+        executor
+    }
+
+    fn outcomes(output: &[u8]) -> Vec<(String, TestMeasure)> {
+        let mut decoder = FrameDecoder::new();
+        decoder.push(output);
+
+        std::iter::from_fn(|| decoder.next_message())
+            .filter_map(|message| match message.unwrap() {
+                BackendMessage::TestCompleted { result } => {
+                    Some((result.name().to_string(), result.outcome().clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Tests whose name starts with "bad" print something and fail, "slow" ones sleep.
+    const FAILING_SCRIPT: &str =
+        r#"read name; case "$name" in bad*) echo wrong;; slow*) sleep 10;; esac"#;
+
+    #[test]
+    fn fail_fast_skips_everything_left() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = grouped_executor(
+            dir.path(),
+            FAILING_SCRIPT,
+            &["bad", "slow", "good"],
+            &[],
+            FailurePolicy::FailFast,
+        );
+
+        let mut output = Vec::new();
+        executor.execute_concurrent(2, None, std::io::empty(), &mut output);
+
+        let outcomes = outcomes(&output);
+        assert!(matches!(outcomes[0], (ref name, TestMeasure::Failure { .. }) if name == "bad"));
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes[1..]
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, TestMeasure::Skipped { .. })));
+    }
+
+    #[test]
+    fn stop_group_keeps_other_tests() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = grouped_executor(
+            dir.path(),
+            FAILING_SCRIPT,
+            &["bad", "grouped", "other"],
+            &["bad", "grouped"],
+            FailurePolicy::StopGroup,
+        );
+
+        let mut output = Vec::new();
+        executor.execute_concurrent(1, None, std::io::empty(), &mut output);
+
+        let outcomes = outcomes(&output);
+        assert!(matches!(outcomes[0].1, TestMeasure::Failure { .. }));
+        assert_eq!(
+            outcomes[1],
+            (
+                "grouped".into(),
+                TestMeasure::Skipped {
+                    reason: "test bad of group group failed".into()
+                }
+            )
+        );
+        assert!(matches!(outcomes[2], (ref name, TestMeasure::Success { .. }) if name == "other"));
+    }
+
+    #[test]
+    fn concurrency_modes() {
+        assert_eq!(concurrent_tests(&Concurrency::Disabled, 8), 1);
+        assert_eq!(concurrent_tests(&Concurrency::Enabled(3), 8), 3);
+        assert_eq!(concurrent_tests(&Concurrency::Auto { reserve: 2 }, 8), 6);
+        assert_eq!(concurrent_tests(&Concurrency::Auto { reserve: 2 }, 1), 1);
+    }
+
+    #[test]
+    fn cancel_stops_running_and_queued_tests() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = executor(dir.path(), "sleep 10", &["first", "second"]);
+        let control = Cursor::new(FrontendMessage::Cancel.serialize());
+
+        let mut output = Vec::new();
+        executor.execute_concurrent(1, None, control, &mut output);
+
+        let mut outcomes = outcomes(&output);
+        outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            outcomes,
+            vec![
+                ("first".into(), TestMeasure::Cancelled),
+                ("second".into(), TestMeasure::Cancelled)
+            ]
+        );
+    }
+
+    #[test]
+    fn cancel_single_queued_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = executor(dir.path(), "true", &["first", "second", "third"]);
+        let control = Cursor::new(
+            FrontendMessage::CancelTest {
+                name: "third".into(),
+            }
+            .serialize(),
+        );
+
+        let mut output = Vec::new();
+        executor.execute_concurrent(1, None, control, &mut output);
+
+        let outcomes = outcomes(&output);
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes.contains(&("third".into(), TestMeasure::Cancelled)));
+        assert!(outcomes
+            .iter()
+            .filter(|(name, _)| name != "third")
+            .all(|(_, outcome)| matches!(outcome, TestMeasure::Success { .. })));
+    }
+
+    #[test]
+    fn repeated_runs_report_timing_and_flakiness() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("counter");
+        // Only the second run of the program, the first measured one, fails.
+        let script = format!(
+            r#"n=$(cat {0} 2>/dev/null || echo 0); echo $((n + 1)) > {0}; [ "$n" = 1 ] && echo wrong; true"#,
+            counter.display()
+        );
+
+        let mut executor = executor(dir.path(), &script, &["test"]);
+        executor.tests = executor
+            .tests
+            .into_iter()
+            .map(|unit| unit.with_repetitions(Repetitions::new(3, 1)))
+            .collect();
+
+        let results = executor.execute_concurrent(1, None, std::io::empty(), &mut Vec::new());
+
+        assert_eq!(std::fs::read_to_string(counter).unwrap().trim(), "4");
+        assert!(matches!(results[0].outcome(), TestMeasure::Failure { .. }));
+        assert!(results[0].is_flaky());
+
+        let timing = results[0].timing().unwrap();
+        assert_eq!(timing.runs, 3);
+        assert!(timing.wall_time.min <= timing.wall_time.median);
+        assert!(timing.wall_time.median <= timing.wall_time.max);
     }
 }
@@ -0,0 +1,85 @@
+use regex::Regex;
+use trst_types::{TestFilters, TestPattern};
+
+/// Compiled form of [`TestFilters`].
+pub struct TestFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl TestFilter {
+    pub fn new(filters: &TestFilters) -> Result<Self, regex::Error> {
+        let compile = |patterns: &[TestPattern]| {
+            patterns
+                .iter()
+                .map(compile_pattern)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            include: compile(&filters.include)?,
+            exclude: compile(&filters.exclude)?,
+        })
+    }
+
+    pub fn selects(&self, test_name: &str) -> bool {
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.is_match(test_name));
+
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.is_match(test_name))
+    }
+}
+
+fn compile_pattern(pattern: &TestPattern) -> Result<Regex, regex::Error> {
+    match pattern {
+        TestPattern::Regex(expression) => Regex::new(&format!("^(?:{expression})$")),
+        TestPattern::Glob(glob) => {
+            let expression = glob
+                .split('*')
+                .map(|part| {
+                    part.split('?')
+                        .map(regex::escape)
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .collect::<Vec<_>>()
+                .join(".*");
+
+            Regex::new(&format!("^{expression}$"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_and_exclude() {
+        let filter = TestFilter::new(&TestFilters {
+            include: vec![
+                TestPattern::Glob("sample*".into()),
+                TestPattern::Regex("stress[0-9]+".into()),
+            ],
+            exclude: vec![TestPattern::Glob("sample?.big".into())],
+        })
+        .unwrap();
+
+        assert!(filter.selects("sample1"));
+        assert!(filter.selects("sample10.big"));
+        assert!(!filter.selects("sample1.big"));
+        assert!(filter.selects("stress42"));
+        assert!(!filter.selects("stress42a"));
+        assert!(!filter.selects("big"));
+
+        let everything = TestFilter::new(&TestFilters::default()).unwrap();
+        assert!(everything.selects("anything"));
+    }
+}
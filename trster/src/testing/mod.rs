@@ -1,25 +1,143 @@
+mod affinity;
+mod control;
 mod executor;
+mod filter;
+mod pool;
+mod process;
 mod test_unit;
-use std::time::Duration;
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+    sync::atomic::AtomicBool,
+    time::Duration,
+};
+
+use trst_types::{BackendMessage, Baseline, Limits, RunSummary, TestResult};
 
 use crate::Config;
 
-pub use self::executor::Paths;
+pub use self::control::spawn_control_listener;
+pub use self::filter::TestFilter;
+
 use self::executor::TestExecutor;
+pub use self::executor::{concurrent_tests, usable_cores, Paths};
+use self::process::RunningProcess;
+pub use self::process::{ProcessRun, Termination};
+pub use self::test_unit::{judge, TestingUnit};
 
 const CHECK_STATUS_INTERVAL: Duration = Duration::from_millis(150);
 
+/// Receiver of everything trster reports while testing.
+pub trait Output {
+    fn emit(&mut self, message: BackendMessage);
+}
+
+/// Writers receive the framed binary stream read by the client.
+impl<W: Write> Output for W {
+    fn emit(&mut self, message: BackendMessage) {
+        self.write_all(&message.serialize()).unwrap();
+        self.flush().unwrap();
+    }
+}
+
 pub trait Executor {
-    fn execute_testing(self, config: &Config);
+    /// Runs every loaded test and returns their results. When a baseline is
+    /// given, the performance of the run is compared with it at the end.
+    /// Frontend requests are read from `control`.
+    fn execute_testing<R: Read + Send + 'static, O: Output>(
+        self,
+        config: &Config,
+        baseline: Option<&Baseline>,
+        control: R,
+        output: &mut O,
+    ) -> Vec<TestResult>;
 }
 
-pub fn load_tests(config: &Config) -> impl Executor {
-    let mut executor = TestExecutor::new().unwrap();
-    let paths = Paths::new(config);
+/// Hello frame of this trster, the first frame of every run.
+pub fn hello() -> BackendMessage {
+    BackendMessage::hello(env!("CARGO_PKG_VERSION"))
+}
+
+/// Splits tests of the configuration into those which pass its filters and,
+/// when `only` is given, are also listed there, and the excluded rest.
+pub fn select_tests<'a>(
+    config: &'a Config,
+    only: Option<&HashSet<String>>,
+) -> (Vec<&'a String>, Vec<String>) {
+    let filter = TestFilter::new(config.filters()).expect("filters are checked by validation");
+    let mut selected = Vec::new();
+    let mut excluded = Vec::new();
 
     for name in config.test_names() {
-        executor.push_test(paths.clone(), name.into());
+        if !filter.selects(name) || only.is_some_and(|only| !only.contains(name)) {
+            excluded.push(name.clone());
+        } else {
+            selected.push(name);
+        }
+    }
+
+    (selected, excluded)
+}
+
+/// Prepares tests selected by [`select_tests`], the rest is reported in the summary.
+pub fn load_tests(config: &Config, only: Option<&HashSet<String>>) -> impl Executor {
+    let mut executor = TestExecutor::new(config.failure_policy());
+    let paths = Paths::new(config);
+    let (selected, excluded) = select_tests(config, only);
+
+    for name in excluded {
+        executor.exclude_test(name);
+    }
+    for name in selected {
+        let group = config.group_of(name).map(|group| group.name().to_string());
+        executor.push_test(
+            paths.clone(),
+            name.into(),
+            group,
+            config.limits_for(name),
+            config.repetitions(),
+        );
     }
 
     executor
 }
+
+/// Prepares a single test of the configuration, filters do not apply.
+pub fn load_test(config: &Config, name: &str) -> Option<TestingUnit> {
+    config.test_names().any(|test| test == name).then(|| {
+        let group = config.group_of(name).map(|group| group.name().to_string());
+        Paths::new(config).unit(name.into(), group, config.limits_for(name))
+    })
+}
+
+/// Emits the frames which end every run: the summary, the baseline comparison
+/// when a baseline is given, and the completion.
+pub fn finish_run<O: Output>(
+    config: &Config,
+    baseline: Option<&Baseline>,
+    results: &[TestResult],
+    excluded: Vec<String>,
+    output: &mut O,
+) {
+    let summary = RunSummary::new(results, excluded);
+    output.emit(BackendMessage::Summary { summary });
+
+    if let Some(baseline) = baseline {
+        let comparison = baseline.compare(results, config.regression_thresholds());
+        output.emit(BackendMessage::BaselineComparison { comparison });
+    }
+
+    output.emit(BackendMessage::TestingProcessCompleted);
+}
+
+/// Runs `program` once with `args` on `input` within `limits`, the way
+/// tested programs are run.
+pub fn run_program(
+    program: &str,
+    args: &[String],
+    input: Vec<u8>,
+    limits: &Limits,
+) -> std::io::Result<ProcessRun> {
+    RunningProcess::spawn(program, args, input, limits, None)?
+        .supervise(limits, &AtomicBool::new(false))
+}
@@ -0,0 +1,150 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use trst_types::{TestMeasure, TestResult};
+
+use super::{
+    executor::ExecutorEvent,
+    test_unit::{TestingUnit, TestingUnitMessage},
+};
+
+/// Fixed set of threads running tests one at a time.
+///
+/// When cores are given, the programs run by worker `n` are pinned to the
+/// `n`-th of them, so parallel tests do not compete for the same core and
+/// their timings stay comparable.
+pub struct WorkerPool {
+    jobs: Sender<TestingUnit>,
+    queue: Arc<Mutex<Receiver<TestingUnit>>>,
+    events: Sender<ExecutorEvent>,
+    cores: Option<Vec<usize>>,
+    workers: usize,
+}
+
+impl WorkerPool {
+    pub fn new(workers: usize, cores: Option<Vec<usize>>, events: Sender<ExecutorEvent>) -> Self {
+        let (jobs, queue) = std::sync::mpsc::channel();
+
+        let mut pool = Self {
+            jobs,
+            queue: Arc::new(Mutex::new(queue)),
+            events,
+            cores: cores.filter(|cores| !cores.is_empty()),
+            workers: 0,
+        };
+        pool.ensure_workers(workers);
+
+        pool
+    }
+
+    /// Starts additional workers until there are at least `workers` of them.
+    /// Pinned pools never grow above the number of their cores, the resulting
+    /// number of workers is returned.
+    pub fn ensure_workers(&mut self, workers: usize) -> usize {
+        let workers = match &self.cores {
+            Some(cores) if workers > cores.len() => {
+                log::warn!(
+                    "{workers} workers requested but only {} cores are available",
+                    cores.len()
+                );
+                cores.len()
+            }
+            _ => workers,
+        };
+
+        while self.workers < workers {
+            let core = self.cores.as_ref().map(|cores| cores[self.workers]);
+
+            let queue = self.queue.clone();
+            let events = self.events.clone();
+            std::thread::spawn(move || Self::work(core, queue, events));
+
+            self.workers += 1;
+        }
+
+        workers
+    }
+
+    pub fn submit(&self, unit: TestingUnit) {
+        self.jobs.send(unit).unwrap();
+    }
+
+    fn work(
+        core: Option<usize>,
+        queue: Arc<Mutex<Receiver<TestingUnit>>>,
+        events: Sender<ExecutorEvent>,
+    ) {
+        loop {
+            // The lock is released as soon as a job is taken.
+            let job = queue.lock().unwrap().recv();
+            let Ok(mut unit) = job else {
+                // Pool was dropped.
+                return;
+            };
+            unit.pin_to(core);
+
+            let name = unit.name().to_string();
+            let unit_events = events.clone();
+
+            let Some(result) = catch_panic(name, move || unit.run_test(unit_events)) else {
+                continue;
+            };
+            if events
+                .send(TestingUnitMessage::Done { result }.into())
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Runs the test `name`, a panic becomes an internal error result of the test
+/// so the worker survives it.
+fn catch_panic(name: String, test: impl FnOnce()) -> Option<TestResult> {
+    let panic = std::panic::catch_unwind(AssertUnwindSafe(test)).err()?;
+    let description = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "testing thread panicked".into());
+
+    Some(TestResult::new(
+        name,
+        TestMeasure::InternalProgramError {
+            description: format!("trster panicked: {description}"),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_becomes_internal_error() {
+        assert_eq!(catch_panic("a".into(), || {}), None);
+
+        let result = catch_panic("a".into(), || panic!("broken {}", 1)).unwrap();
+        assert_eq!(result.name(), "a");
+        assert_eq!(
+            *result.outcome(),
+            TestMeasure::InternalProgramError {
+                description: "trster panicked: broken 1".into()
+            }
+        );
+
+        let result = catch_panic("b".into(), || std::panic::panic_any(3)).unwrap();
+        assert_eq!(
+            *result.outcome(),
+            TestMeasure::InternalProgramError {
+                description: "trster panicked: testing thread panicked".into()
+            }
+        );
+    }
+}
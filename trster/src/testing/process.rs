@@ -0,0 +1,266 @@
+use std::{
+    io::{Read, Write},
+    os::unix::process::{CommandExt, ExitStatusExt},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use trst_types::Limits;
+
+use super::{affinity, CHECK_STATUS_INTERVAL};
+
+const READ_CHUNK: usize = 8 * 1024;
+
+#[derive(Debug)]
+pub enum Termination {
+    Exited(ExitStatus),
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    OutputLimitExceeded,
+    Cancelled,
+}
+
+/// Everything known about a finished (or killed) process.
+#[derive(Debug)]
+pub struct ProcessRun {
+    pub termination: Termination,
+    pub wall_time: Duration,
+    /// User and system time of the process.
+    pub cpu_time: Duration,
+    /// Peak resident set size in bytes.
+    pub peak_memory: u64,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Result of reaping the child and the moment it was reaped.
+type ProcessExit = (std::io::Result<(ExitStatus, libc::rusage)>, Instant);
+
+/// Child process fed with prepared input whose resources are watched until it ends.
+pub struct RunningProcess {
+    child: Child,
+    started: Instant,
+    exit: Receiver<ProcessExit>,
+    output_exceeded: Arc<AtomicBool>,
+    stdin_writer: JoinHandle<()>,
+    stdout_reader: JoinHandle<Vec<u8>>,
+    stderr_reader: JoinHandle<Vec<u8>>,
+}
+
+impl RunningProcess {
+    /// Starts the program, pinned to `core` when one is given.
+    pub fn spawn(
+        program: &str,
+        args: &[String],
+        input: Vec<u8>,
+        limits: &Limits,
+        core: Option<usize>,
+    ) -> std::io::Result<Self> {
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Own process group, so killing it also stops everything the program spawned.
+            .process_group(0);
+        if let Some(core) = core {
+            affinity::pin_child(&mut command, core);
+        }
+        let mut child = command.spawn()?;
+
+        let started = Instant::now();
+
+        let pid = child.id() as libc::pid_t;
+        let (exit_tx, exit) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let finished = wait4(pid);
+            let _ = exit_tx.send((finished, Instant::now()));
+        });
+
+        let output_exceeded = Arc::new(AtomicBool::new(false));
+
+        let mut stdin = child.stdin.take().unwrap();
+        // Closing stdin after writing lets programs reading until EOF finish.
+        // Broken pipe only means the program did not consume the whole input.
+        let stdin_writer = std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+
+        let stdout_reader = Self::capture(
+            child.stdout.take().unwrap(),
+            limits.output(),
+            output_exceeded.clone(),
+        );
+        let stderr_reader = Self::capture(
+            child.stderr.take().unwrap(),
+            limits.output(),
+            output_exceeded.clone(),
+        );
+
+        Ok(Self {
+            child,
+            started,
+            exit,
+            output_exceeded,
+            stdin_writer,
+            stdout_reader,
+            stderr_reader,
+        })
+    }
+
+    fn capture<R: Read + Send + 'static>(
+        mut stream: R,
+        limit: Option<u64>,
+        exceeded: Arc<AtomicBool>,
+    ) -> JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut captured = Vec::new();
+            let mut chunk = [0; READ_CHUNK];
+
+            loop {
+                let read = match stream.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                };
+
+                if limit.is_some_and(|limit| (captured.len() + read) as u64 > limit) {
+                    exceeded.store(true, Ordering::Relaxed);
+                    break;
+                }
+
+                captured.extend_from_slice(&chunk[..read]);
+            }
+
+            captured
+        })
+    }
+
+    fn pid(&self) -> libc::pid_t {
+        self.child.id() as libc::pid_t
+    }
+
+    fn kill_group(&self) {
+        // SAFETY: plain syscall, a negative pid addresses the child's process group.
+        unsafe {
+            libc::kill(-self.pid(), libc::SIGKILL);
+        }
+    }
+
+    /// Current peak resident memory in bytes, read while the process is alive.
+    fn current_peak_memory(&self) -> Option<u64> {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", self.pid())).ok()?;
+
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|value| {
+                value
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .map(|kilobytes| kilobytes * 1024)
+    }
+
+    /// Watches the process until it exits, breaks one of the limits or `cancel` is set.
+    pub fn supervise(self, limits: &Limits, cancel: &AtomicBool) -> std::io::Result<ProcessRun> {
+        let mut violation = None;
+
+        let (finished, exited) = loop {
+            // Limits are checked periodically, while the exit itself is noticed right away.
+            let remaining = limits.time().saturating_sub(self.started.elapsed());
+            match self.exit.recv_timeout(CHECK_STATUS_INTERVAL.min(remaining)) {
+                Ok(exit) => break exit,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(std::io::Error::other("process waiting thread stopped"))
+                }
+            }
+
+            if cancel.load(Ordering::Relaxed) {
+                violation = Some(Termination::Cancelled);
+            } else if self.started.elapsed() >= limits.time() {
+                violation = Some(Termination::TimeLimitExceeded);
+            } else if self.output_exceeded.load(Ordering::Relaxed) {
+                violation = Some(Termination::OutputLimitExceeded);
+            } else if let (Some(limit), Some(peak)) = (limits.memory(), self.current_peak_memory())
+            {
+                if peak > limit {
+                    violation = Some(Termination::MemoryLimitExceeded);
+                }
+            }
+
+            if violation.is_some() {
+                self.kill_group();
+                break self.exit.recv().map_err(std::io::Error::other)?;
+            }
+        };
+
+        let (status, usage) = finished?;
+        let wall_time = exited.duration_since(self.started);
+        let _ = self.stdin_writer.join();
+        let stdout = self.stdout_reader.join().unwrap_or_default();
+        let stderr = self.stderr_reader.join().unwrap_or_default();
+
+        let cpu_time = timeval_to_duration(usage.ru_utime) + timeval_to_duration(usage.ru_stime);
+        // ru_maxrss is reported in kilobytes on Linux.
+        let peak_memory = usage.ru_maxrss as u64 * 1024;
+
+        let termination = match violation {
+            Some(violation) => violation,
+            None if wall_time > limits.time() => Termination::TimeLimitExceeded,
+            None if self.output_exceeded.load(Ordering::Relaxed) => {
+                Termination::OutputLimitExceeded
+            }
+            None if limits.memory().is_some_and(|limit| peak_memory > limit) => {
+                Termination::MemoryLimitExceeded
+            }
+            None => Termination::Exited(status),
+        };
+
+        Ok(ProcessRun {
+            termination,
+            wall_time,
+            cpu_time,
+            peak_memory,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+/// Blocks until the child exits and reaps it together with its resource usage.
+fn wait4(pid: libc::pid_t) -> std::io::Result<(ExitStatus, libc::rusage)> {
+    let mut status = 0;
+    // SAFETY: rusage is a plain C struct for which all zeroes is a valid value.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    loop {
+        // SAFETY: pid belongs to our not yet reaped child, pointers are valid for the call.
+        let result = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+
+        if result != -1 {
+            return Ok((ExitStatus::from_raw(status), usage));
+        }
+
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
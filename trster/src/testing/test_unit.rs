@@ -1,12 +1,10 @@
 use std::{
-    io::Write,
-    process::{Child, Command, Stdio},
-    sync::{mpsc::Sender, Arc},
-    time::SystemTime,
+    process::ExitStatus,
+    sync::{atomic::AtomicBool, mpsc::Sender, Arc},
 };
 use trst_types::*;
 
-use super::CHECK_STATUS_INTERVAL;
+use super::process::{ProcessRun, RunningProcess, Termination};
 
 #[derive(Debug)]
 pub enum TestingUnitMessage {
@@ -33,6 +31,11 @@ pub struct TestingUnit {
     err_test_path: Arc<String>,
 
     name: String,
+    group: Option<String>,
+    limits: Limits,
+    repetitions: Repetitions,
+    cancellation: Arc<AtomicBool>,
+    core: Option<usize>,
 }
 
 impl TestingUnit {
@@ -43,6 +46,8 @@ impl TestingUnit {
         err_test_path: Arc<String>,
 
         name: String,
+        group: Option<String>,
+        limits: Limits,
     ) -> Self {
         Self {
             program_path,
@@ -50,8 +55,49 @@ impl TestingUnit {
             out_test_path,
             err_test_path,
             name,
+            group,
+            limits,
+            repetitions: Repetitions::default(),
+            cancellation: Arc::new(AtomicBool::new(false)),
+            core: None,
         }
     }
+
+    pub fn with_repetitions(mut self, repetitions: Repetitions) -> Self {
+        self.repetitions = repetitions;
+        self
+    }
+
+    /// Runs the program of the test on `core` only.
+    pub fn pin_to(&mut self, core: Option<usize>) {
+        self.core = core;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Flag which, once set, kills the test program and reports the test as cancelled.
+    pub fn cancellation(&self) -> Arc<AtomicBool> {
+        self.cancellation.clone()
+    }
+
+    pub fn report_cancelled(self) -> TestingUnitMessage {
+        TestingUnitMessage::Done {
+            result: TestResult::new(self.name, TestMeasure::Cancelled),
+        }
+    }
+
+    pub fn report_skipped(self, reason: String) -> TestingUnitMessage {
+        TestingUnitMessage::Done {
+            result: TestResult::new(self.name, TestMeasure::Skipped { reason }),
+        }
+    }
+
     fn report_internal_error<E: ToString>(test_name: String, error: E) -> TestResult {
         let measure = TestMeasure::InternalProgramError {
             description: error.to_string(),
@@ -60,12 +106,6 @@ impl TestingUnit {
         TestResult::new(test_name, measure)
     }
 
-    fn report_timing_out(test_name: String) -> TestResult {
-        let measure = TestMeasure::Timeout;
-
-        TestResult::new(test_name, measure)
-    }
-
     fn in_file_path(&self) -> String {
         if self.in_test_path.ends_with('/') {
             format!("{}{}.in", self.in_test_path, self.name)
@@ -90,167 +130,215 @@ impl TestingUnit {
         }
     }
 
-    fn construct_child(
-        &self,
-        channel_status_report: &Sender<TestingUnitMessage>,
-    ) -> Result<(Child, SystemTime), TestingUnitMessage> {
-        let mut command = Command::new(self.program_path.as_ref());
-
-        // Program's streams settings
-        command
-            .stdin(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdout(Stdio::piped());
-
-        let in_path = self.in_file_path();
-        let in_file = std::fs::read(&in_path).map_err(|e| TestingUnitMessage::Done {
-            result: Self::report_internal_error(self.name.clone(), e),
-        })?;
-
-        let mut spawned_command = command.spawn().map_err(|e| TestingUnitMessage::Done {
-            result: Self::report_internal_error(self.name.clone(), e),
-        })?;
+    /// Input of the test read from its file.
+    pub fn input(&self) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.in_file_path())
+    }
 
-        channel_status_report
-            .send(TestingUnitMessage::StartedExecution {
-                name: self.name.clone(),
-            })
-            .unwrap();
+    /// Runs the program once on `input` instead of the input of the test. The
+    /// outputs are compared with `expected` standard and error output when
+    /// given, and with the expected files of the test otherwise.
+    pub fn run_input(&self, input: Vec<u8>, expected: Option<(&str, &str)>) -> TestMeasure {
+        self.run_once(input, expected).0
+    }
 
-        spawned_command
-            .stdin
-            .as_mut()
-            .unwrap()
-            .write_all(in_file.as_ref())
-            .map_err(|e| TestingUnitMessage::Done {
-                result: Self::report_internal_error(self.name.clone(), e),
-            })?;
+    /// Runs the program once and judges its output. Runs which did not end
+    /// because of an internal error also return their measurement.
+    fn run_once(
+        &self,
+        input: Vec<u8>,
+        expected: Option<(&str, &str)>,
+    ) -> (TestMeasure, Option<ProcessRun>) {
+        let run = RunningProcess::spawn(&self.program_path, &[], input, &self.limits, self.core)
+            .and_then(|process| process.supervise(&self.limits, &self.cancellation));
+        let run = match run {
+            Ok(run) => run,
+            Err(e) => {
+                return (
+                    TestMeasure::InternalProgramError {
+                        description: e.to_string(),
+                    },
+                    None,
+                )
+            }
+        };
 
-        let started_time = SystemTime::now();
+        let measure = match expected {
+            Some((out, err)) => judge(&run, || Ok(out.into()), || Ok(err.into())),
+            None => judge(
+                &run,
+                || std::fs::read_to_string(self.out_file_path()),
+                || std::fs::read_to_string(self.err_file_path()),
+            ),
+        };
 
-        Ok((spawned_command, started_time))
+        (measure, Some(run))
     }
 
-    pub fn run_test(self, channel_status_report: Sender<TestingUnitMessage>) {
-        let (mut spawned_command, started_time) = match self.construct_child(&channel_status_report)
-        {
-            Ok(result) => result,
+    pub fn run_test<E: From<TestingUnitMessage>>(self, channel_status_report: Sender<E>) {
+        let input = match std::fs::read(self.in_file_path()) {
+            Ok(input) => input,
             Err(e) => {
-                channel_status_report.send(e).unwrap();
+                let result = Self::report_internal_error(self.name, e);
+                channel_status_report
+                    .send(TestingUnitMessage::Done { result }.into())
+                    .unwrap();
                 return;
             }
         };
 
-        let exited_time;
-        loop {
-            log::trace!("LOOP ENTRY. [{:?}]", spawned_command.try_wait());
-            match spawned_command.try_wait() {
-                Ok(None) => {
-                    log::trace!("Checking time = [{:?}]", crate::TEST_TIMEOUT);
-                    if SystemTime::now().duration_since(started_time).unwrap()
-                        > *crate::TEST_TIMEOUT.get().unwrap()
-                    {
-                        let _ = spawned_command.kill();
-
-                        channel_status_report
-                            .send(TestingUnitMessage::Done {
-                                result: Self::report_timing_out(self.name),
-                            })
-                            .unwrap();
-                        return;
-                    } else {
-                        // TODO: Check if this is working correctly
-                        // TODO: Make this interruptible by process ending (another thread can be blocked waiting on this and some channels)
-                        std::thread::sleep(CHECK_STATUS_INTERVAL);
-                    }
+        channel_status_report
+            .send(
+                TestingUnitMessage::StartedExecution {
+                    name: self.name.clone(),
                 }
+                .into(),
+            )
+            .unwrap();
 
-                Err(_) => {
-                    todo!();
-                }
+        for _ in 0..self.repetitions.warmup() {
+            if let (TestMeasure::Cancelled, _) = self.run_once(input.clone(), None) {
+                channel_status_report
+                    .send(self.report_cancelled().into())
+                    .unwrap();
+                return;
+            }
+        }
 
-                Ok(_) => {
-                    exited_time = SystemTime::now();
-                    break;
-                }
+        let mut verdicts = Vec::new();
+        let mut runs = Vec::new();
+        for _ in 0..self.repetitions.runs() {
+            let (measure, run) = self.run_once(input.clone(), None);
+            if let TestMeasure::Cancelled = measure {
+                channel_status_report
+                    .send(self.report_cancelled().into())
+                    .unwrap();
+                return;
             }
+
+            verdicts.push(measure);
+            runs.extend(run);
         }
 
-        let command_output = spawned_command.wait_with_output().unwrap();
+        let timing = Self::timing(&runs);
+        let flaky = verdicts
+            .windows(2)
+            .any(|pair| std::mem::discriminant(&pair[0]) != std::mem::discriminant(&pair[1]));
+
+        // A single failing repetition fails the test, otherwise the median time is reported.
+        let outcome = match verdicts.iter().position(TestMeasure::is_failure) {
+            Some(failed) => verdicts.swap_remove(failed),
+            None => match verdicts.swap_remove(0) {
+                TestMeasure::Success { exit_status, .. } => TestMeasure::Success {
+                    time: timing
+                        .as_ref()
+                        .map(|timing| timing.wall_time.median)
+                        .unwrap_or_default(),
+                    exit_status,
+                },
+                other => other,
+            },
+        };
+
+        let result = TestResult::new(self.name, outcome)
+            .with_timing(timing)
+            .with_flaky(flaky);
 
         channel_status_report
-            .send(
-                self.check_outcome(
-                    command_output,
-                    exited_time
-                        .duration_since(started_time)
-                        .unwrap()
-                        .as_secs_f64(),
-                ),
-            )
+            .send(TestingUnitMessage::Done { result }.into())
             .unwrap();
     }
 
-    fn failed_test(
-        self,
-        stdout: String,
-        stderr: String,
-        exit_status: Option<i32>,
-    ) -> TestingUnitMessage {
-        TestingUnitMessage::Done {
-            result: TestResult::new(
-                self.name,
-                TestMeasure::Failure {
-                    stdout,
-                    stderr,
-                    exit_status,
-                },
-            ),
-        }
-    }
+    fn timing(runs: &[ProcessRun]) -> Option<Timing> {
+        let wall_times: Vec<_> = runs.iter().map(|run| run.wall_time.as_secs_f64()).collect();
+        let cpu_times: Vec<_> = runs.iter().map(|run| run.cpu_time.as_secs_f64()).collect();
 
-    fn check_outcome(self, output: std::process::Output, elapsed_time: f64) -> TestingUnitMessage {
-        let out_file = match std::fs::read_to_string(self.out_file_path()) {
-            Ok(content) => content,
-            Err(e) => {
-                return TestingUnitMessage::Done {
-                    result: Self::report_internal_error(self.name, e),
-                };
-            }
-        };
+        Some(Timing {
+            runs: runs.len() as u32,
+            wall_time: Statistics::from_samples(&wall_times)?,
+            cpu_time: Statistics::from_samples(&cpu_times)?,
+            peak_memory: runs.iter().map(|run| run.peak_memory).max()?,
+        })
+    }
+}
 
-        let program_stdout = String::from_utf8(output.stdout).unwrap();
-        let program_stderr = String::from_utf8(output.stderr).unwrap();
+/// Judges a run the way every test is judged: exceeded limits first, then
+/// the standard output and error output compared with the expected ones.
+/// An expected output is only read once everything before it matched.
+pub fn judge(
+    run: &ProcessRun,
+    expected_out: impl FnOnce() -> std::io::Result<String>,
+    expected_err: impl FnOnce() -> std::io::Result<String>,
+) -> TestMeasure {
+    match run.termination {
+        Termination::TimeLimitExceeded => TestMeasure::Timeout,
+        Termination::MemoryLimitExceeded => TestMeasure::MemoryLimitExceeded {
+            peak_memory: run.peak_memory,
+        },
+        Termination::OutputLimitExceeded => TestMeasure::OutputLimitExceeded,
+        Termination::Cancelled => TestMeasure::Cancelled,
+        Termination::Exited(status) => check_outcome(
+            &run.stdout,
+            &run.stderr,
+            status,
+            run.wall_time.as_secs_f64(),
+            expected_out,
+            expected_err,
+        ),
+    }
+}
 
-        if program_stdout != out_file {
-            // TODO: Segmentation Fault detection
-            return self.failed_test(program_stdout, program_stderr, output.status.code());
+fn check_outcome(
+    stdout: &[u8],
+    stderr: &[u8],
+    status: ExitStatus,
+    elapsed_time: f64,
+    expected_out: impl FnOnce() -> std::io::Result<String>,
+    expected_err: impl FnOnce() -> std::io::Result<String>,
+) -> TestMeasure {
+    let out_file = match expected_out() {
+        Ok(content) => content,
+        Err(e) => {
+            return TestMeasure::InternalProgramError {
+                description: e.to_string(),
+            };
         }
+    };
 
-        drop(out_file);
+    let program_stdout = String::from_utf8_lossy(stdout).into_owned();
+    let program_stderr = String::from_utf8_lossy(stderr).into_owned();
 
-        let err_file = match std::fs::read_to_string(self.err_file_path()) {
-            Ok(content) => content,
-            Err(e) => {
-                return TestingUnitMessage::Done {
-                    result: Self::report_internal_error(self.name, e),
-                };
-            }
+    if program_stdout != out_file {
+        // TODO: Segmentation Fault detection
+        return TestMeasure::Failure {
+            stdout: program_stdout,
+            stderr: program_stderr,
+            exit_status: status.code(),
         };
+    }
 
-        if program_stderr != err_file {
-            // TODO: Segmentation Fault detection
-            return self.failed_test(program_stdout, program_stderr, output.status.code());
-        }
+    drop(out_file);
 
-        TestingUnitMessage::Done {
-            result: TestResult::new(
-                self.name,
-                TestMeasure::Success {
-                    time: elapsed_time,
-                    exit_status: output.status.code(),
-                },
-            ),
+    let err_file = match expected_err() {
+        Ok(content) => content,
+        Err(e) => {
+            return TestMeasure::InternalProgramError {
+                description: e.to_string(),
+            };
         }
+    };
+
+    if program_stderr != err_file {
+        // TODO: Segmentation Fault detection
+        return TestMeasure::Failure {
+            stdout: program_stdout,
+            stderr: program_stderr,
+            exit_status: status.code(),
+        };
+    }
+
+    TestMeasure::Success {
+        time: elapsed_time,
+        exit_status: status.code(),
     }
 }
@@ -0,0 +1,254 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{TestMeasure, TestResult};
+
+/// Timing and memory of a passing test, recorded for later comparison.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct BaselineEntry {
+    /// Median wall time of the measured runs in seconds.
+    pub time: f64,
+    /// Median CPU time in seconds, unknown for results without timing.
+    pub cpu_time: Option<f64>,
+    /// Peak resident memory in bytes, unknown for results without timing.
+    pub peak_memory: Option<u64>,
+}
+
+impl BaselineEntry {
+    /// `None` unless the test passed, other verdicts say nothing about performance.
+    pub fn from_result(result: &TestResult) -> Option<Self> {
+        let TestMeasure::Success { time, .. } = result.outcome() else {
+            return None;
+        };
+
+        Some(match result.timing() {
+            Some(timing) => Self {
+                time: timing.wall_time.median,
+                cpu_time: Some(timing.cpu_time.median),
+                peak_memory: Some(timing.peak_memory),
+            },
+            None => Self {
+                time: *time,
+                cpu_time: None,
+                peak_memory: None,
+            },
+        })
+    }
+}
+
+/// Performance of every test in a reference run, keyed by test name.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Baseline {
+    tests: BTreeMap<String, BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn from_results(results: &[TestResult]) -> Self {
+        let mut baseline = Self::default();
+        baseline.update(results);
+        baseline
+    }
+
+    /// Replaces entries of the passing tests, the rest is kept untouched.
+    pub fn update(&mut self, results: &[TestResult]) {
+        for result in results {
+            if let Some(entry) = BaselineEntry::from_result(result) {
+                self.tests.insert(result.name().to_string(), entry);
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Records performance of the passing tests in the baseline file. Entries of
+    /// tests which did not run or failed this time are kept.
+    pub fn save_results(path: &Path, results: &[TestResult]) -> std::io::Result<()> {
+        let mut baseline = match Self::load(path) {
+            Ok(baseline) => baseline,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e),
+        };
+        baseline.update(results);
+
+        let content = serde_json::to_vec_pretty(&baseline)?;
+        std::fs::write(path, content)
+    }
+
+    pub fn get(&self, test_name: &str) -> Option<&BaselineEntry> {
+        self.tests.get(test_name)
+    }
+
+    /// Compares passing tests of a run with their baseline entries.
+    pub fn compare(
+        &self,
+        results: &[TestResult],
+        thresholds: &RegressionThresholds,
+    ) -> BaselineComparison {
+        let mut comparison = BaselineComparison::default();
+
+        for result in results {
+            let Some(current) = BaselineEntry::from_result(result) else {
+                continue;
+            };
+
+            match self.get(result.name()) {
+                Some(baseline) => comparison.tests.push(TestComparison {
+                    name: result.name().to_string(),
+                    regressed: thresholds.is_regression(baseline.time, current.time),
+                    baseline: baseline.clone(),
+                    current,
+                }),
+                None => comparison.new_tests.push(result.name().to_string()),
+            }
+        }
+
+        comparison
+    }
+}
+
+/// Slowdowns above which a test is reported as a regression. A test is
+/// flagged when it exceeds any of the thresholds which are set.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct RegressionThresholds {
+    /// Allowed slowdown relative to the baseline time, in percent.
+    pub percent: Option<f64>,
+    /// Allowed slowdown in seconds.
+    pub seconds: Option<f64>,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            percent: Some(10.0),
+            seconds: None,
+        }
+    }
+}
+
+impl RegressionThresholds {
+    pub fn is_regression(&self, baseline_time: f64, time: f64) -> bool {
+        let slowdown = time - baseline_time;
+
+        let relative = self
+            .percent
+            .is_some_and(|percent| slowdown > baseline_time * percent / 100.0);
+        let absolute = self.seconds.is_some_and(|seconds| slowdown > seconds);
+
+        relative || absolute
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TestComparison {
+    pub name: String,
+    pub baseline: BaselineEntry,
+    pub current: BaselineEntry,
+    /// Slower than the configured thresholds allow.
+    pub regressed: bool,
+}
+
+impl TestComparison {
+    /// Change of the wall time in seconds, positive when the test got slower.
+    pub fn time_delta(&self) -> f64 {
+        self.current.time - self.baseline.time
+    }
+
+    /// Change of the wall time relative to the baseline, in percent.
+    pub fn time_delta_percent(&self) -> Option<f64> {
+        (self.baseline.time > 0.0).then(|| self.time_delta() / self.baseline.time * 100.0)
+    }
+
+    /// Change of the peak memory in bytes, when both runs measured it.
+    pub fn memory_delta(&self) -> Option<i64> {
+        Some(self.current.peak_memory? as i64 - self.baseline.peak_memory? as i64)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct BaselineComparison {
+    /// Passing tests which have a baseline entry, in the order of results.
+    pub tests: Vec<TestComparison>,
+    /// Passing tests missing from the baseline.
+    pub new_tests: Vec<String>,
+}
+
+impl BaselineComparison {
+    pub fn regressions(&self) -> impl Iterator<Item = &TestComparison> {
+        self.tests.iter().filter(|test| test.regressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::passed, Statistics, Timing};
+
+    #[test]
+    fn thresholds() {
+        let percent = RegressionThresholds::default();
+        assert!(!percent.is_regression(1.0, 1.05));
+        assert!(percent.is_regression(1.0, 1.2));
+
+        let both = RegressionThresholds {
+            percent: Some(50.0),
+            seconds: Some(0.05),
+        };
+        assert!(both.is_regression(1.0, 1.06));
+        assert!(both.is_regression(0.01, 0.02));
+        assert!(!both.is_regression(1.0, 0.5));
+
+        let none = RegressionThresholds {
+            percent: None,
+            seconds: None,
+        };
+        assert!(!none.is_regression(1.0, 100.0));
+    }
+
+    #[test]
+    fn comparison() {
+        let timing = Timing {
+            runs: 3,
+            wall_time: Statistics::from_samples(&[1.0, 2.0, 9.0]).unwrap(),
+            cpu_time: Statistics::from_samples(&[1.0]).unwrap(),
+            peak_memory: 1000,
+        };
+
+        let mut baseline = Baseline::from_results(&[
+            passed("fast", 1.0).with_timing(Some(timing)),
+            passed("slow", 1.0),
+            TestResult::new("broken".into(), TestMeasure::Timeout),
+        ]);
+        assert_eq!(baseline.get("fast").unwrap().time, 2.0);
+        assert_eq!(baseline.get("fast").unwrap().peak_memory, Some(1000));
+        assert!(baseline.get("broken").is_none());
+
+        let run = [
+            passed("fast", 1.0),
+            passed("slow", 1.5),
+            passed("new", 1.0),
+            TestResult::new("broken".into(), TestMeasure::Timeout),
+        ];
+        let comparison = baseline.compare(&run, &RegressionThresholds::default());
+
+        assert_eq!(comparison.tests.len(), 2);
+        assert_eq!(comparison.tests[0].time_delta(), -1.0);
+        assert_eq!(comparison.tests[0].memory_delta(), None);
+        assert_eq!(comparison.tests[1].time_delta_percent(), Some(50.0));
+        assert_eq!(
+            comparison
+                .regressions()
+                .map(|test| &test.name)
+                .collect::<Vec<_>>(),
+            ["slow"]
+        );
+        assert_eq!(comparison.new_tests, ["new"]);
+
+        baseline.update(&run);
+        assert_eq!(baseline.get("slow").unwrap().time, 1.5);
+        assert_eq!(baseline.get("fast").unwrap().peak_memory, None);
+    }
+}
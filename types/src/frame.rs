@@ -0,0 +1,343 @@
+use std::{fmt::Display, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{BackendMessage, FrontendMessage};
+
+/// Every frame starts with its payload length as a big-endian u32.
+const LENGTH_PREFIX: usize = u32::BITS as usize / 8;
+
+/// Frames above this size are treated as a corrupted stream.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Serializes a message into a single length-prefixed frame.
+pub fn encode_frame<M: Serialize>(message: &M) -> Vec<u8> {
+    let frame = postcard::to_extend(message, vec![0; LENGTH_PREFIX]).unwrap();
+    let size = (frame.len() - LENGTH_PREFIX) as u32;
+
+    let mut frame = frame;
+    frame[..LENGTH_PREFIX].copy_from_slice(&size.to_be_bytes());
+    frame
+}
+
+impl BackendMessage {
+    pub fn serialize(&self) -> Vec<u8> {
+        encode_frame(self)
+    }
+}
+
+impl FrontendMessage {
+    pub fn serialize(&self) -> Vec<u8> {
+        encode_frame(self)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Announced frame length is above the limit. The stream cannot be
+    /// resynchronised afterwards, so the decoder stops producing messages.
+    FrameTooLarge { size: usize, max: usize },
+    /// Frame was complete but its payload did not decode. The frame is
+    /// skipped and decoding continues with the next one.
+    Malformed { description: String },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FrameTooLarge { size, max } => {
+                write!(f, "frame of {size} bytes exceeds the limit of {max} bytes")
+            }
+            Self::Malformed { description } => write!(f, "malformed frame: {description}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Incremental decoder of length-prefixed frames.
+///
+/// Input may be pushed in chunks of any size, complete messages are then
+/// taken out with [`FrameDecoder::next_message`].
+#[derive(Debug)]
+pub struct FrameDecoder<M = BackendMessage> {
+    buffer: Vec<u8>,
+    start: usize,
+    max_frame_size: usize,
+    failed: bool,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<M: DeserializeOwned> Default for FrameDecoder<M> {
+    fn default() -> Self {
+        Self::with_max_frame_size(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl<M: DeserializeOwned> FrameDecoder<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            start: 0,
+            max_frame_size,
+            failed: false,
+            message: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        if self.failed {
+            return;
+        }
+
+        // Drop consumed bytes only once they dominate the buffer, so that
+        // memmove cost stays amortised over many frames.
+        if self.start > 0 && self.start >= self.buffer.len() / 2 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// True after a fatal error, no more messages will be produced.
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Number of received bytes not yet decoded.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// Next complete message, `None` if more input is needed.
+    pub fn next_message(&mut self) -> Option<Result<M, DecodeError>> {
+        if self.failed {
+            return None;
+        }
+
+        let pending = &self.buffer[self.start..];
+        if pending.len() < LENGTH_PREFIX {
+            return None;
+        }
+
+        let mut prefix = [0; LENGTH_PREFIX];
+        prefix.copy_from_slice(&pending[..LENGTH_PREFIX]);
+        let size = u32::from_be_bytes(prefix) as usize;
+
+        if size > self.max_frame_size {
+            self.failed = true;
+            self.buffer = Vec::new();
+            self.start = 0;
+
+            return Some(Err(DecodeError::FrameTooLarge {
+                size,
+                max: self.max_frame_size,
+            }));
+        }
+
+        if pending.len() < LENGTH_PREFIX + size {
+            return None;
+        }
+
+        let payload = &pending[LENGTH_PREFIX..LENGTH_PREFIX + size];
+        let message = postcard::from_bytes(payload).map_err(|e| DecodeError::Malformed {
+            description: e.to_string(),
+        });
+        self.start += LENGTH_PREFIX + size;
+
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        Baseline, ConfigIssue, RegressionThresholds, RunSummary, Statistics, TestMeasure,
+        TestResult, Timing,
+    };
+
+    fn decode_all(decoder: &mut FrameDecoder) -> Vec<Result<BackendMessage, DecodeError>> {
+        std::iter::from_fn(|| decoder.next_message()).collect()
+    }
+
+    #[test]
+    fn split_and_concatenated_frames() {
+        let first = BackendMessage::ExecutionStarted {
+            test_name: "test1".into(),
+        };
+        let second = BackendMessage::ExecutionStarted {
+            test_name: "tak".into(),
+        };
+
+        let mut bytes = first.serialize();
+        bytes.extend(first.serialize());
+        bytes.extend(second.serialize());
+        bytes.push(0);
+
+        let mut decoder = FrameDecoder::new();
+        for chunk in bytes.chunks(3) {
+            decoder.push(chunk);
+        }
+
+        assert_eq!(
+            decode_all(&mut decoder),
+            vec![
+                Ok(BackendMessage::ExecutionStarted {
+                    test_name: "test1".into()
+                }),
+                Ok(BackendMessage::ExecutionStarted {
+                    test_name: "test1".into()
+                }),
+                Ok(second),
+            ]
+        );
+        assert_eq!(decoder.buffered(), 1);
+    }
+
+    #[test]
+    fn malformed_frame_is_skipped() {
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&[0, 0, 0, 2, 200, 200]);
+        decoder.push(&BackendMessage::TestingProcessCompleted.serialize());
+
+        let messages = decode_all(&mut decoder);
+        assert!(matches!(messages[0], Err(DecodeError::Malformed { .. })));
+        assert_eq!(messages[1], Ok(BackendMessage::TestingProcessCompleted));
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn oversized_frame_stops_decoding() {
+        let mut decoder: FrameDecoder = FrameDecoder::with_max_frame_size(16);
+        decoder.push(&[0, 0, 1, 0]);
+
+        assert_eq!(
+            decoder.next_message(),
+            Some(Err(DecodeError::FrameTooLarge { size: 256, max: 16 }))
+        );
+
+        decoder.push(&BackendMessage::TestingProcessCompleted.serialize());
+        assert!(decoder.is_failed());
+        assert_eq!(decoder.next_message(), None);
+    }
+
+    fn measure() -> impl Strategy<Value = TestMeasure> {
+        let status = proptest::option::of(any::<i32>());
+
+        prop_oneof![
+            (
+                any::<f64>().prop_filter("NaN is not equal to itself", |time| !time.is_nan()),
+                status.clone()
+            )
+                .prop_map(|(time, exit_status)| TestMeasure::Success { time, exit_status }),
+            (".*", ".*", status).prop_map(|(stdout, stderr, exit_status)| TestMeasure::Failure {
+                stdout,
+                stderr,
+                exit_status
+            }),
+            ".*".prop_map(|description| TestMeasure::InternalProgramError { description }),
+            Just(TestMeasure::Timeout),
+            any::<u64>().prop_map(|peak_memory| TestMeasure::MemoryLimitExceeded { peak_memory }),
+            Just(TestMeasure::OutputLimitExceeded),
+            Just(TestMeasure::Cancelled),
+            ".*".prop_map(|reason| TestMeasure::Skipped { reason }),
+        ]
+    }
+
+    fn result() -> impl Strategy<Value = TestResult> {
+        let seconds = || any::<f64>().prop_filter("NaN is not equal to itself", |x| !x.is_nan());
+        let statistics = move || {
+            (seconds(), seconds(), seconds(), seconds(), seconds()).prop_map(
+                |(min, median, mean, stddev, max)| Statistics {
+                    min,
+                    median,
+                    mean,
+                    stddev,
+                    max,
+                },
+            )
+        };
+        let timing = (any::<u32>(), statistics(), statistics(), any::<u64>()).prop_map(
+            |(runs, wall_time, cpu_time, peak_memory)| Timing {
+                runs,
+                wall_time,
+                cpu_time,
+                peak_memory,
+            },
+        );
+
+        (".*", measure(), proptest::option::of(timing), any::<bool>()).prop_map(
+            |(name, outcome, timing, flaky)| {
+                TestResult::new(name, outcome)
+                    .with_timing(timing)
+                    .with_flaky(flaky)
+            },
+        )
+    }
+
+    fn message() -> impl Strategy<Value = BackendMessage> {
+        prop_oneof![
+            (any::<u32>(), ".*", proptest::collection::vec(".*", 0..4)).prop_map(
+                |(protocol_version, trster_version, capabilities)| BackendMessage::Hello {
+                    protocol_version,
+                    trster_version,
+                    capabilities
+                }
+            ),
+            ".*".prop_map(|test_name| BackendMessage::ExecutionStarted { test_name }),
+            result().prop_map(|result| BackendMessage::TestCompleted { result }),
+            Just(BackendMessage::TestingProcessCompleted),
+            proptest::collection::vec(result(), 0..4).prop_map(|results| {
+                BackendMessage::Summary {
+                    summary: RunSummary::new(&results, vec!["excluded".into()]),
+                }
+            }),
+            (
+                proptest::collection::vec(result(), 0..4),
+                proptest::collection::vec(result(), 0..4)
+            )
+                .prop_map(|(reference, results)| {
+                    let comparison = Baseline::from_results(&reference)
+                        .compare(&results, &RegressionThresholds::default());
+                    BackendMessage::BaselineComparison { comparison }
+                }),
+            (".*", proptest::collection::vec(".*", 0..4)).prop_map(|(description, names)| {
+                BackendMessage::ConfigRejected {
+                    description,
+                    issues: names
+                        .into_iter()
+                        .map(|name| ConfigIssue::DuplicateTest { name })
+                        .collect(),
+                }
+            }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(messages in proptest::collection::vec(message(), 0..8), chunk in 1usize..64) {
+            let bytes: Vec<u8> = messages.iter().flat_map(BackendMessage::serialize).collect();
+
+            let mut decoder = FrameDecoder::new();
+            for piece in bytes.chunks(chunk) {
+                decoder.push(piece);
+            }
+
+            let decoded = decode_all(&mut decoder)
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            prop_assert_eq!(decoded, messages);
+            prop_assert_eq!(decoder.buffered(), 0);
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Config, RunSummary, RunningMode, TestResult};
+
+const HISTORY_FILE: &str = "runs.jsonl";
+const LOCK_FILE: &str = "runs.lock";
+
+/// Runs [`RunHistory::record`] lets the history grow past `max_runs` before
+/// trimming it, so a full history is not rewritten after every run.
+const TRIM_SLACK: usize = 10;
+
+/// Everything recorded about one completed run.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RunRecord {
+    /// Seconds since the Unix epoch when the run finished.
+    pub timestamp: u64,
+    pub config: Config,
+    /// SHA-256 of the tested program in hex, when it could be read.
+    pub program_hash: Option<String>,
+    pub results: Vec<TestResult>,
+}
+
+impl RunRecord {
+    pub fn summary(&self) -> RunSummary {
+        RunSummary::new(&self.results, Vec::new())
+    }
+}
+
+/// Which runs are kept in the history, the newest ones always win.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retention {
+    pub max_runs: Option<usize>,
+    pub max_age: Option<Duration>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_runs: Some(100),
+            max_age: None,
+        }
+    }
+}
+
+/// Append-only store of completed runs, one JSON record per line.
+///
+/// Writers hold an advisory lock on a file next to the history, so runs
+/// recorded concurrently by several processes are all kept.
+pub struct RunHistory {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl RunHistory {
+    /// Opens the history kept in `dir`, creating the directory if needed.
+    pub fn open(dir: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        Ok(Self {
+            path: dir.join(HISTORY_FILE),
+            lock_path: dir.join(LOCK_FILE),
+        })
+    }
+
+    /// Appends a run which just finished and drops runs the retention policy
+    /// does not keep, returns the number of dropped runs. Up to `TRIM_SLACK`
+    /// runs over `max_runs` are dropped together.
+    pub fn record(
+        &self,
+        config: &Config,
+        results: &[TestResult],
+        retention: &Retention,
+    ) -> std::io::Result<usize> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let program_hash = match config.running_mode() {
+            RunningMode::Local {
+                compiled_program_path,
+                ..
+            } => std::fs::read(compiled_program_path)
+                .ok()
+                .map(|program| format!("{:x}", Sha256::digest(program))),
+            RunningMode::GitRepository { .. } => None,
+        };
+
+        let _lock = self.lock()?;
+        self.write_record(&RunRecord {
+            timestamp,
+            config: config.clone(),
+            program_hash,
+            results: results.to_vec(),
+        })?;
+
+        self.trim(retention, timestamp, TRIM_SLACK)
+    }
+
+    pub fn append(&self, record: &RunRecord) -> std::io::Result<()> {
+        let _lock = self.lock()?;
+        self.write_record(record)
+    }
+
+    /// Every readable record, oldest first. Lines which cannot be parsed,
+    /// such as one cut short by a crash, are skipped.
+    pub fn load(&self) -> std::io::Result<Vec<RunRecord>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Drops runs which the retention policy does not keep and returns their number.
+    /// `now` is the current time in seconds since the Unix epoch.
+    pub fn apply_retention(&self, retention: &Retention, now: u64) -> std::io::Result<usize> {
+        let _lock = self.lock()?;
+        self.trim(retention, now, 0)
+    }
+
+    /// Blocks until no other writer uses the history, which lasts until the
+    /// returned file is dropped.
+    fn lock(&self) -> std::io::Result<File> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock_path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    fn write_record(&self, record: &RunRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        // A single write keeps readers from seeing a partial record.
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&line)
+    }
+
+    /// Applies the retention policy, the history must be locked. Runs over
+    /// `max_runs` are only dropped once there are more than `slack` of them.
+    fn trim(&self, retention: &Retention, now: u64, slack: usize) -> std::io::Result<usize> {
+        let mut records = self.load()?;
+        let count = records.len();
+
+        if let Some(max_age) = retention.max_age {
+            let oldest = now.saturating_sub(max_age.as_secs());
+            records.retain(|record| record.timestamp >= oldest);
+        }
+        if let Some(max_runs) = retention.max_runs {
+            if records.len() < count || records.len() > max_runs + slack {
+                records.drain(..records.len().saturating_sub(max_runs));
+            }
+        }
+
+        let removed = count - records.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        let mut content = Vec::new();
+        for record in &records {
+            serde_json::to_writer(&mut content, record)?;
+            content.push(b'\n');
+        }
+
+        // Readers never see a partially rewritten history.
+        let temporary = self
+            .path
+            .with_extension(format!("jsonl.{}.tmp", std::process::id()));
+        std::fs::write(&temporary, content)?;
+        std::fs::rename(temporary, &self.path)?;
+
+        Ok(removed)
+    }
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Civil date from days since the epoch, see Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::local_config, TestMeasure};
+
+    fn record(timestamp: u64) -> RunRecord {
+        RunRecord {
+            timestamp,
+            config: local_config(&["a"]),
+            program_hash: None,
+            results: vec![TestResult::new("a".into(), TestMeasure::Timeout)],
+        }
+    }
+
+    #[test]
+    fn append_load_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = RunHistory::open(&dir.path().join("history")).unwrap();
+        assert!(history.load().unwrap().is_empty());
+
+        for timestamp in [100, 200, 300, 400] {
+            history.append(&record(timestamp)).unwrap();
+        }
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&history.path)
+            .unwrap()
+            .write_all(b"{\"timestamp\": 5")
+            .unwrap();
+
+        let records = history.load().unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].summary().failed(), 1);
+
+        let retention = Retention {
+            max_runs: Some(2),
+            max_age: Some(Duration::from_secs(250)),
+        };
+        assert_eq!(history.apply_retention(&retention, 500).unwrap(), 2);
+        let timestamps: Vec<_> = history
+            .load()
+            .unwrap()
+            .iter()
+            .map(|record| record.timestamp)
+            .collect();
+        assert_eq!(timestamps, [300, 400]);
+
+        assert_eq!(history.apply_retention(&retention, 500).unwrap(), 0);
+    }
+
+    #[test]
+    fn record_hashes_program() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("program");
+        std::fs::write(&program, "abc").unwrap();
+        let config = local_config(&["a"]).with_running_mode(RunningMode::Local {
+            in_test_path: "t".into(),
+            out_test_path: "t".into(),
+            err_test_path: "t".into(),
+            compiled_program_path: program.to_string_lossy().into_owned(),
+        });
+
+        let history = RunHistory::open(dir.path()).unwrap();
+        let retention = Retention {
+            max_runs: Some(1),
+            max_age: None,
+        };
+        for _ in 0..=TRIM_SLACK {
+            assert_eq!(history.record(&config, &[], &retention).unwrap(), 0);
+        }
+        assert_eq!(
+            history.record(&config, &[], &retention).unwrap(),
+            TRIM_SLACK + 1
+        );
+
+        let records = history.load().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].program_hash.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
+    #[test]
+    fn concurrent_writers_keep_every_run() {
+        const RUNS: usize = 30;
+
+        let dir = tempfile::tempdir().unwrap();
+        let retention = Retention {
+            max_runs: Some(5),
+            max_age: None,
+        };
+
+        let writers: Vec<_> = (0..2)
+            .map(|_| {
+                let dir = dir.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let history = RunHistory::open(&dir).unwrap();
+                    let config = local_config(&["a"]);
+                    (0..RUNS)
+                        .map(|_| history.record(&config, &[], &retention).unwrap())
+                        .sum::<usize>()
+                })
+            })
+            .collect();
+        let removed: usize = writers.into_iter().map(|w| w.join().unwrap()).sum();
+
+        let kept = RunHistory::open(dir.path()).unwrap().load().unwrap().len();
+        assert!(kept <= 5 + TRIM_SLACK);
+        assert_eq!(removed + kept, 2 * RUNS);
+        let leftovers = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_709_168_523), "2024-02-29 01:02:03 UTC");
+    }
+}
@@ -1,8 +1,25 @@
-use std::{collections::VecDeque, fmt::Display};
+use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+mod baseline;
+mod frame;
+mod history;
+mod protocol;
+mod remote;
+mod run_comparison;
+mod session;
+#[cfg(test)]
+mod test_support;
+pub use baseline::*;
+pub use frame::*;
+pub use history::*;
+pub use protocol::*;
+pub use remote::*;
+pub use run_comparison::*;
+pub use session::*;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum TestMeasure {
     Success {
         time: f64,
@@ -17,19 +34,125 @@ pub enum TestMeasure {
         description: String,
     },
     Timeout,
+    MemoryLimitExceeded {
+        peak_memory: u64,
+    },
+    OutputLimitExceeded,
+    /// Stopped or never started because of a frontend request.
+    Cancelled,
+    /// Stopped or never started because of an earlier failure.
+    Skipped {
+        reason: String,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl TestMeasure {
+    /// Short lowercase description of the verdict.
+    pub fn verdict(&self) -> &'static str {
+        match self {
+            Self::Success { .. } => "passed",
+            Self::Failure { .. } => "wrong answer",
+            Self::InternalProgramError { .. } => "internal error",
+            Self::Timeout => "time limit exceeded",
+            Self::MemoryLimitExceeded { .. } => "memory limit exceeded",
+            Self::OutputLimitExceeded => "output limit exceeded",
+            Self::Cancelled => "cancelled",
+            Self::Skipped { .. } => "skipped",
+        }
+    }
+
+    /// True for verdicts which show that the tested program is wrong.
+    pub fn is_failure(&self) -> bool {
+        match self {
+            Self::Success { .. } | Self::Cancelled | Self::Skipped { .. } => false,
+            Self::Failure { .. }
+            | Self::InternalProgramError { .. }
+            | Self::Timeout
+            | Self::MemoryLimitExceeded { .. }
+            | Self::OutputLimitExceeded => true,
+        }
+    }
+}
+
+/// Summary of repeated measurements of one quantity.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Statistics {
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    /// Population standard deviation.
+    pub stddev: f64,
+    pub max: f64,
+}
+
+impl Statistics {
+    /// `None` for an empty slice.
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let count = sorted.len();
+        let median = if count.is_multiple_of(2) {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        } else {
+            sorted[count / 2]
+        };
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / count as f64;
+
+        Some(Self {
+            min: sorted[0],
+            median,
+            mean,
+            stddev: variance.sqrt(),
+            max: sorted[count - 1],
+        })
+    }
+}
+
+/// Resources used by the measured runs of a test, times in seconds.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Timing {
+    pub runs: u32,
+    pub wall_time: Statistics,
+    pub cpu_time: Statistics,
+    /// Highest peak resident memory in bytes.
+    pub peak_memory: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct TestResult {
     name: String,
     outcome: TestMeasure,
+    #[serde(default)]
+    timing: Option<Timing>,
+    /// Repetitions of the test did not all end with the same verdict.
+    #[serde(default)]
+    flaky: bool,
 }
 
-const SIZE_LEN: u32 = u32::BITS / 8;
-
 impl TestResult {
     pub fn new(name: String, outcome: TestMeasure) -> Self {
-        Self { name, outcome }
+        Self {
+            name,
+            outcome,
+            timing: None,
+            flaky: false,
+        }
+    }
+
+    pub fn with_timing(mut self, timing: Option<Timing>) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    pub fn with_flaky(mut self, flaky: bool) -> Self {
+        self.flaky = flaky;
+        self
     }
 
     pub fn name(&self) -> &str {
@@ -39,22 +162,209 @@ impl TestResult {
     pub fn outcome(&self) -> &TestMeasure {
         &self.outcome
     }
+
+    pub fn timing(&self) -> Option<&Timing> {
+        self.timing.as_ref()
+    }
+
+    /// Median wall time of the measured runs in seconds, if the program was run.
+    pub fn time(&self) -> Option<f64> {
+        match (&self.timing, &self.outcome) {
+            (Some(timing), _) => Some(timing.wall_time.median),
+            (None, TestMeasure::Success { time, .. }) => Some(*time),
+            _ => None,
+        }
+    }
+
+    pub fn is_flaky(&self) -> bool {
+        self.flaky
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum BackendMessage {
-    ExecutionStarted { test_name: String },
-    TestCompleted { result: TestResult },
+    /// First frame of every stream. It must stay the first variant and keep
+    /// its fields, so that any build can decode it and compare versions.
+    Hello {
+        protocol_version: u32,
+        trster_version: String,
+        capabilities: Vec<String>,
+    },
+    ExecutionStarted {
+        test_name: String,
+    },
+    TestCompleted {
+        result: TestResult,
+    },
     TestingProcessCompleted,
+    /// Totals of the run, written just before [`BackendMessage::TestingProcessCompleted`].
+    Summary {
+        summary: RunSummary,
+    },
+    /// Performance compared with a baseline, written after [`BackendMessage::Summary`]
+    /// when trster was given a baseline.
+    BaselineComparison {
+        comparison: BaselineComparison,
+    },
+    /// Smallest input found by `trster minimize` on which the test still fails
+    /// the way it failed on its own input.
+    InputMinimized {
+        test_name: String,
+        /// Raw bytes of the input, it does not have to be text.
+        input: Vec<u8>,
+        /// Size of the input of the test in bytes.
+        original_size: u64,
+        measure: TestMeasure,
+    },
+    /// Written after [`BackendMessage::Hello`] instead of testing when the
+    /// configuration cannot be used. There are no issues when it could not
+    /// even be read or parsed.
+    ConfigRejected {
+        description: String,
+        issues: Vec<ConfigIssue>,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct RunSummary {
+    passed: u64,
+    failed: u64,
+    /// Skipped or cancelled tests.
+    not_run: u64,
+    /// Tests of the configuration left out by filters.
+    excluded: Vec<String>,
+}
+
+impl RunSummary {
+    pub fn new(results: &[TestResult], excluded: Vec<String>) -> Self {
+        let mut summary = Self {
+            excluded,
+            ..Self::default()
+        };
+
+        for result in results {
+            match result.outcome() {
+                TestMeasure::Success { .. } => summary.passed += 1,
+                outcome if outcome.is_failure() => summary.failed += 1,
+                _ => summary.not_run += 1,
+            }
+        }
+
+        summary
+    }
+
+    pub fn passed(&self) -> u64 {
+        self.passed
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed
+    }
+
+    pub fn not_run(&self) -> u64 {
+        self.not_run
+    }
+
+    pub fn excluded(&self) -> &[String] {
+        &self.excluded
+    }
+}
+
+/// Commands sent by the frontend to a running trster on its stdin.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum FrontendMessage {
+    /// Stops every running test and drops the queued ones.
+    Cancel,
+    CancelTest {
+        name: String,
+    },
+    /// Stops starting new tests, already running ones are finished.
+    Pause,
+    Resume,
+    /// Changes the number of tests run at once, running tests are not interrupted.
+    SetConcurrency(u64),
+}
+
+/// Problem of a configuration found before the first test is started.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum ConfigIssue {
+    MissingDirectory {
+        field: String,
+        path: String,
+    },
+    MissingProgram {
+        path: String,
+    },
+    DuplicateTest {
+        name: String,
+    },
+    InvalidPattern {
+        error: String,
+    },
+    UnsupportedMode {
+        mode: String,
+    },
+    /// The time limit is not a positive number of seconds a test can wait for.
+    InvalidTimeLimit {
+        field: String,
+        time: String,
+    },
+}
+
+impl Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingDirectory { field, path } => {
+                write!(f, "{field}: directory '{path}' does not exist")
+            }
+            Self::MissingProgram { path } => write!(f, "program '{path}' does not exist"),
+            Self::DuplicateTest { name } => write!(f, "test '{name}' is listed more than once"),
+            Self::InvalidPattern { error } => write!(f, "invalid test filter: {error}"),
+            Self::UnsupportedMode { mode } => write!(f, "{mode} is not supported yet"),
+            Self::InvalidTimeLimit { field, time } => {
+                write!(
+                    f,
+                    "{field}: time limit {time} is not a valid number of seconds"
+                )
+            }
+        }
+    }
 }
 
 // TRSTER CONFIG STANDARD
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Language {
     Cpp,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+impl Language {
+    pub const ALL: [Language; 1] = [Language::Cpp];
+
+    /// Compiler invoked as `<compiler> <flags> -o <binary> <source>`.
+    pub fn compiler(&self) -> &'static str {
+        match self {
+            Self::Cpp => "g++",
+        }
+    }
+
+    pub fn default_flags(&self) -> &'static str {
+        match self {
+            Self::Cpp => "-O2 -std=c++17",
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Cpp => "C++",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub enum RunningMode {
     GitRepository {
         address: String,
@@ -71,122 +381,406 @@ impl Display for RunningMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let name = match self {
             Self::GitRepository { .. } => "Git repository mode",
-            Self::Local {..} => "Local mode"
+            Self::Local { .. } => "Local mode",
         };
 
         write!(f, "{name}")
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Concurrency {
     Disabled,
     Enabled(u64),
+    /// One test per usable core, leaving `reserve` cores for the rest of the system.
+    Auto {
+        reserve: u64,
+    },
+}
+
+/// Pattern matched against whole test names.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum TestPattern {
+    /// `*` matches any sequence of characters and `?` any single character.
+    Glob(String),
+    Regex(String),
+}
+
+/// Selects tests of `test_list` to run. Without include patterns every test
+/// is included, exclude patterns are applied afterwards.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestFilters {
+    #[serde(default)]
+    pub include: Vec<TestPattern>,
+    #[serde(default)]
+    pub exclude: Vec<TestPattern>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// What happens to the remaining tests once one of them fails.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    #[default]
+    RunAll,
+    /// Remaining tests of the failed test's group are skipped.
+    StopGroup,
+    /// Every remaining test is skipped.
+    FailFast,
+}
+
+/// How many times every test is run. Warm-up runs are not measured and
+/// their verdicts are ignored.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repetitions {
+    runs: u32,
+    warmup: u32,
+}
+
+impl Default for Repetitions {
+    fn default() -> Self {
+        Self { runs: 1, warmup: 0 }
+    }
+}
+
+impl Repetitions {
+    pub fn new(runs: u32, warmup: u32) -> Self {
+        Self { runs, warmup }
+    }
+
+    /// Measured runs, at least one.
+    pub fn runs(&self) -> u32 {
+        self.runs.max(1)
+    }
+
+    pub fn warmup(&self) -> u32 {
+        self.warmup
+    }
+}
+
+/// Placement of test programs on the CPU.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuOptions {
+    /// Every running test gets a core of its own, which also caps the concurrency.
+    pin_tests: bool,
+    /// Only the first logical CPU of every physical core is used.
+    skip_smt_siblings: bool,
+}
+
+impl Default for CpuOptions {
+    fn default() -> Self {
+        Self {
+            pin_tests: true,
+            skip_smt_siblings: false,
+        }
+    }
+}
+
+impl CpuOptions {
+    pub fn new(pin_tests: bool, skip_smt_siblings: bool) -> Self {
+        Self {
+            pin_tests,
+            skip_smt_siblings,
+        }
+    }
+
+    pub fn pin_tests(&self) -> bool {
+        self.pin_tests
+    }
+
+    pub fn skip_smt_siblings(&self) -> bool {
+        self.skip_smt_siblings
+    }
+}
+
+const DEFAULT_TIME_LIMIT: f64 = 2.0;
+const DEFAULT_MEMORY_LIMIT: u64 = 256 * 1024 * 1024;
+const DEFAULT_OUTPUT_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Resource limits applied to a single test execution.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Wall time limit in seconds.
+    time: f64,
+    /// Peak resident memory in bytes, `None` means unlimited.
+    memory: Option<u64>,
+    /// Bytes the program may write to each of stdout and stderr, `None` means unlimited.
+    output: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            time: DEFAULT_TIME_LIMIT,
+            memory: Some(DEFAULT_MEMORY_LIMIT),
+            output: Some(DEFAULT_OUTPUT_LIMIT),
+        }
+    }
+}
+
+impl Limits {
+    pub fn new(time: f64, memory: Option<u64>, output: Option<u64>) -> Self {
+        Self {
+            time,
+            memory,
+            output,
+        }
+    }
+
+    pub fn time(&self) -> Duration {
+        Duration::from_secs_f64(self.time)
+    }
+
+    pub fn memory(&self) -> Option<u64> {
+        self.memory
+    }
+
+    pub fn output(&self) -> Option<u64> {
+        self.output
+    }
+
+    fn apply(&mut self, overrides: &LimitOverrides) {
+        if let Some(time) = overrides.time {
+            self.time = time;
+        }
+
+        if let Some(memory) = overrides.memory {
+            self.memory = Some(memory);
+        }
+
+        if let Some(output) = overrides.output {
+            self.output = Some(output);
+        }
+    }
+}
+
+/// Partial [`Limits`], every field that is set replaces the inherited value.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct LimitOverrides {
+    #[serde(default)]
+    pub time: Option<f64>,
+    #[serde(default)]
+    pub memory: Option<u64>,
+    #[serde(default)]
+    pub output: Option<u64>,
+}
+
+/// Named set of tests sharing limit overrides.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TestGroup {
+    name: String,
+    tests: Vec<String>,
+    #[serde(default)]
+    limits: LimitOverrides,
+}
+
+impl TestGroup {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn contains(&self, test_name: &str) -> bool {
+        self.tests.iter().any(|name| name == test_name)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Config {
     mode: RunningMode,
     test_list: Vec<String>,
     language: Language,
     concurrency: Concurrency,
+    #[serde(default)]
+    filters: TestFilters,
+    #[serde(default)]
+    cpu: CpuOptions,
+    #[serde(default)]
+    on_failure: FailurePolicy,
+    #[serde(default)]
+    repetitions: Repetitions,
+    #[serde(default)]
+    regression: RegressionThresholds,
+    #[serde(default)]
+    limits: Limits,
+    #[serde(default)]
+    groups: Vec<TestGroup>,
+    #[serde(default)]
+    test_limits: HashMap<String, LimitOverrides>,
 }
 
 impl Config {
-    pub fn concurrency_settings(&self) -> &Concurrency {
-        &self.concurrency
+    /// Configuration with defaults for everything but the essentials.
+    pub fn new(
+        mode: RunningMode,
+        test_list: Vec<String>,
+        language: Language,
+        concurrency: Concurrency,
+    ) -> Self {
+        Self {
+            mode,
+            test_list,
+            language,
+            concurrency,
+            filters: TestFilters::default(),
+            cpu: CpuOptions::default(),
+            on_failure: FailurePolicy::default(),
+            repetitions: Repetitions::default(),
+            regression: RegressionThresholds::default(),
+            limits: Limits::default(),
+            groups: Vec::new(),
+            test_limits: HashMap::new(),
+        }
     }
 
-    pub fn test_names(&self) -> impl Iterator<Item = &String> {
-        self.test_list.iter()
+    pub fn with_cpu_options(mut self, cpu: CpuOptions) -> Self {
+        self.cpu = cpu;
+        self
     }
 
-    pub fn running_mode(&self) -> &RunningMode {
-        &self.mode
+    pub fn with_concurrency(mut self, concurrency: Concurrency) -> Self {
+        self.concurrency = concurrency;
+        self
     }
-}
 
-#[test]
-fn tak() {
-    let vals: [u8; 64] = [
-        0, 5, 116, 101, 115, 116, 49, 1, 5, 116, 101, 115, 116, 49, 1, 2, 53, 10, 0, 0, 0, 0, 0, 0,
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    ];
-
-    let mut vd = VecDeque::new();
-    for i in vals {
-        vd.push_back(i);
+    pub fn with_failure_policy(mut self, on_failure: FailurePolicy) -> Self {
+        self.on_failure = on_failure;
+        self
     }
 
-    println!("FIRST {:?}", BackendMessage::try_deserialize(&mut vd));
+    pub fn with_running_mode(mut self, mode: RunningMode) -> Self {
+        self.mode = mode;
+        self
+    }
 
-    let bm = BackendMessage::ExecutionStarted {
-        test_name: "test1".into(),
-    };
-    let x = bm.serialize();
-    println!("X = {x:?}");
-    let mut vd = VecDeque::new();
+    pub fn with_test_list(mut self, test_list: Vec<String>) -> Self {
+        self.test_list = test_list;
+        self
+    }
 
-    for i in x.iter() {
-        vd.push_back(*i);
+    pub fn concurrency_settings(&self) -> &Concurrency {
+        &self.concurrency
     }
 
-    for i in x.iter() {
-        vd.push_back(*i);
+    pub fn filters(&self) -> &TestFilters {
+        &self.filters
     }
 
-    let bm = BackendMessage::ExecutionStarted {
-        test_name: "tak".into(),
-    };
-    let x = bm.serialize();
+    pub fn cpu_options(&self) -> &CpuOptions {
+        &self.cpu
+    }
 
-    for i in x.iter() {
-        vd.push_back(*i);
+    pub fn repetitions(&self) -> Repetitions {
+        self.repetitions
     }
 
-    vd.push_back(0);
+    pub fn regression_thresholds(&self) -> &RegressionThresholds {
+        &self.regression
+    }
 
-    println!("{:?}", x);
-    println!("{:?}", BackendMessage::try_deserialize(&mut vd));
-    println!("{:?}", x);
-}
+    pub fn failure_policy(&self) -> FailurePolicy {
+        self.on_failure
+    }
 
-impl BackendMessage {
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut serialized = postcard::to_allocvec(&self).unwrap();
-        let size = serialized.len() as u32;
+    pub fn test_names(&self) -> impl Iterator<Item = &String> {
+        self.test_list.iter()
+    }
 
-        let mut result = Vec::new();
-        size.to_be_bytes()
-            .into_iter()
-            .for_each(|num| result.push(num));
-        result.append(&mut serialized);
+    pub fn running_mode(&self) -> &RunningMode {
+        &self.mode
+    }
 
-        result
+    pub fn language(&self) -> Language {
+        self.language
     }
 
-    pub fn try_deserialize(bytes: &mut VecDeque<u8>) -> Option<Self> {
-        if bytes.len() < 4 {
-            return None;
-        }
+    /// Group the test belongs to, the first one listing it wins.
+    pub fn group_of(&self, test_name: &str) -> Option<&TestGroup> {
+        self.groups.iter().find(|group| group.contains(test_name))
+    }
 
-        let size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    /// Every time limit set in the configuration, named by the field it is set in.
+    pub fn time_limits(&self) -> Vec<(String, f64)> {
+        let mut limits = vec![("limits.time".to_string(), self.limits.time)];
 
-        if size + 4 > bytes.len() as u32 {
-            return None;
+        for group in &self.groups {
+            if let Some(time) = group.limits.time {
+                limits.push((format!("groups.{}.limits.time", group.name), time));
+            }
         }
 
-        for _ in 0..SIZE_LEN {
-            bytes.pop_front();
+        let mut tests: Vec<_> = self.test_limits.iter().collect();
+        tests.sort_by_key(|(name, _)| *name);
+        for (name, overrides) in tests {
+            if let Some(time) = overrides.time {
+                limits.push((format!("test_limits.{name}.time"), time));
+            }
         }
 
-        let mut self_data = Vec::new();
+        limits
+    }
+
+    /// Effective limits of the test: defaults, then its group, then the test itself.
+    pub fn limits_for(&self, test_name: &str) -> Limits {
+        let mut limits = self.limits;
+
+        if let Some(group) = self.group_of(test_name) {
+            limits.apply(&group.limits);
+        }
 
-        for _ in 0..size {
-            self_data.push(bytes.pop_front().unwrap());
+        if let Some(overrides) = self.test_limits.get(test_name) {
+            limits.apply(overrides);
         }
 
-        Some(postcard::from_bytes(&self_data).unwrap())
+        limits
     }
 }
+
+#[test]
+fn statistics() {
+    let statistics = Statistics::from_samples(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+    assert_eq!(statistics.min, 1.0);
+    assert_eq!(statistics.median, 2.5);
+    assert_eq!(statistics.mean, 2.5);
+    assert_eq!(statistics.stddev, 1.25f64.sqrt());
+    assert_eq!(statistics.max, 4.0);
+
+    assert_eq!(Statistics::from_samples(&[7.0]).unwrap().median, 7.0);
+    assert!(Statistics::from_samples(&[]).is_none());
+}
+
+#[test]
+fn limits_resolution() {
+    let config: Config = ron::from_str(
+        r#"(
+            mode: Local(
+                in_test_path: "in",
+                out_test_path: "out",
+                err_test_path: "err",
+                compiled_program_path: "prog",
+            ),
+            test_list: ["sample1", "stress1", "stress2"],
+            language: Cpp,
+            concurrency: Disabled,
+            limits: (time: 1.0, memory: Some(1024), output: None),
+            groups: [(name: "stress", tests: ["stress1", "stress2"], limits: (time: Some(10.0)))],
+            test_limits: {"stress2": (memory: Some(4096))},
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.limits_for("sample1"),
+        Limits::new(1.0, Some(1024), None)
+    );
+    assert_eq!(
+        config.limits_for("stress1"),
+        Limits::new(10.0, Some(1024), None)
+    );
+    assert_eq!(
+        config.limits_for("stress2"),
+        Limits::new(10.0, Some(4096), None)
+    );
+    assert_eq!(
+        config.group_of("stress2").map(TestGroup::name),
+        Some("stress")
+    );
+    assert!(config.group_of("sample1").is_none());
+}
@@ -0,0 +1,211 @@
+use std::fmt::Display;
+
+use crate::BackendMessage;
+
+/// Version of the trster output stream, bumped on every incompatible change
+/// of [`BackendMessage`] or of the framing.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Optional features of the stream announced in [`BackendMessage::Hello`].
+/// Names unknown to the receiver are ignored.
+pub mod capabilities {
+    /// Memory and output limits are enforced and reported as separate verdicts.
+    pub const RESOURCE_LIMITS: &str = "resource-limits";
+    /// [`crate::FrontendMessage`] frames written to trster stdin are obeyed.
+    pub const CONTROL_CHANNEL: &str = "control-channel";
+    /// [`crate::BackendMessage::Summary`] is written at the end of the run.
+    pub const RUN_SUMMARY: &str = "run-summary";
+    /// [`crate::BackendMessage::BaselineComparison`] is written when a baseline is given.
+    pub const BASELINE_COMPARISON: &str = "baseline-comparison";
+    /// [`crate::BackendMessage::InputMinimized`] is written by `trster minimize`.
+    pub const INPUT_MINIMIZATION: &str = "input-minimization";
+    /// [`crate::BackendMessage::ConfigRejected`] is written for an unusable configuration.
+    pub const CONFIG_ERRORS: &str = "config-errors";
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// The first frame of the stream was not a [`BackendMessage::Hello`].
+    MissingHello,
+    VersionMismatch {
+        expected: u32,
+        found: u32,
+        trster_version: String,
+    },
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHello => write!(f, "stream does not start with a hello frame"),
+            Self::VersionMismatch {
+                expected,
+                found,
+                trster_version,
+            } => write!(
+                f,
+                "protocol version {found} of trster {trster_version} is not supported, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// Peer information taken from an accepted [`BackendMessage::Hello`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handshake {
+    trster_version: String,
+    capabilities: Vec<String>,
+}
+
+impl Handshake {
+    pub fn trster_version(&self) -> &str {
+        &self.trster_version
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|name| name == capability)
+    }
+}
+
+impl BackendMessage {
+    /// Hello frame of a trster of version `trster_version`, the first frame
+    /// it writes. The version is reported for diagnostics only.
+    pub fn hello(trster_version: &str) -> Self {
+        Self::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            trster_version: trster_version.into(),
+            capabilities: vec![
+                capabilities::RESOURCE_LIMITS.into(),
+                capabilities::CONTROL_CHANNEL.into(),
+                capabilities::RUN_SUMMARY.into(),
+                capabilities::BASELINE_COMPARISON.into(),
+                capabilities::INPUT_MINIMIZATION.into(),
+                capabilities::CONFIG_ERRORS.into(),
+            ],
+        }
+    }
+
+    /// Checks that the first frame of a stream comes from a compatible trster.
+    pub fn accept_hello(self) -> Result<Handshake, ProtocolError> {
+        match self {
+            Self::Hello {
+                protocol_version,
+                trster_version,
+                ..
+            } if protocol_version != PROTOCOL_VERSION => Err(ProtocolError::VersionMismatch {
+                expected: PROTOCOL_VERSION,
+                found: protocol_version,
+                trster_version,
+            }),
+            Self::Hello {
+                trster_version,
+                capabilities,
+                ..
+            } => Ok(Handshake {
+                trster_version,
+                capabilities,
+            }),
+            _ => Err(ProtocolError::MissingHello),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FrameDecoder, FrontendMessage, TestMeasure, TestResult};
+
+    fn decode(bytes: &[u8]) -> BackendMessage {
+        let mut decoder = FrameDecoder::new();
+        decoder.push(bytes);
+        let message = decoder.next_message().unwrap().unwrap();
+        assert_eq!(decoder.buffered(), 0);
+        message
+    }
+
+    // The fixtures pin the wire format of version 2. If one of them has to
+    // change, PROTOCOL_VERSION has to be bumped together with it.
+
+    #[test]
+    fn hello_fixture() {
+        let bytes = [
+            0, 0, 0, 25, 0, 2, 5, b'0', b'.', b'1', b'.', b'0', 1, 15, b'r', b'e', b's', b'o',
+            b'u', b'r', b'c', b'e', b'-', b'l', b'i', b'm', b'i', b't', b's',
+        ];
+
+        let handshake = decode(&bytes).accept_hello().unwrap();
+        assert_eq!(handshake.trster_version(), "0.1.0");
+        assert!(handshake.supports(capabilities::RESOURCE_LIMITS));
+    }
+
+    #[test]
+    fn future_version_is_rejected() {
+        let bytes = [0, 0, 0, 9, 0, 3, 5, b'9', b'.', b'9', b'.', b'9', 0];
+
+        assert_eq!(
+            decode(&bytes).accept_hello(),
+            Err(ProtocolError::VersionMismatch {
+                expected: PROTOCOL_VERSION,
+                found: 3,
+                trster_version: "9.9.9".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn message_fixtures() {
+        let started = [0, 0, 0, 7, 1, 5, b't', b'e', b's', b't', b'1'];
+        assert!(matches!(
+            decode(&started),
+            BackendMessage::ExecutionStarted { test_name } if test_name == "test1"
+        ));
+        assert_eq!(
+            decode(&started).accept_hello(),
+            Err(ProtocolError::MissingHello)
+        );
+
+        let completed = [0, 0, 0, 8, 2, 3, b't', b'a', b'k', 3, 0, 0];
+        match decode(&completed) {
+            BackendMessage::TestCompleted { result } => {
+                assert_eq!(result.name(), "tak");
+                assert!(matches!(result.outcome(), TestMeasure::Timeout));
+            }
+            other => panic!("unexpected message {other:?}"),
+        }
+
+        assert!(matches!(
+            decode(&[0, 0, 0, 1, 3]),
+            BackendMessage::TestingProcessCompleted
+        ));
+    }
+
+    #[test]
+    fn frontend_fixtures() {
+        let mut decoder = FrameDecoder::<FrontendMessage>::new();
+        decoder.push(&[0, 0, 0, 1, 0, 0, 0, 0, 2, 4, 3, 0, 0, 0, 3, 1, 1, b'a']);
+
+        assert_eq!(decoder.next_message(), Some(Ok(FrontendMessage::Cancel)));
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(FrontendMessage::SetConcurrency(3)))
+        );
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(FrontendMessage::CancelTest { name: "a".into() }))
+        );
+    }
+
+    #[test]
+    fn fixtures_match_serializer() {
+        let message = BackendMessage::TestCompleted {
+            result: TestResult::new("tak".into(), TestMeasure::Timeout),
+        };
+
+        assert_eq!(
+            message.serialize(),
+            [0, 0, 0, 8, 2, 3, b't', b'a', b'k', 3, 0, 0]
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Config, RunningMode};
+
+const CONFIG_FILE: &str = "config.json";
+pub(crate) const PROGRAM_FILE: &str = "program";
+
+/// Connection settings of a host tests are run on over SSH.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshSettings {
+    pub host: String,
+    /// Login of the SSH configuration when empty.
+    pub user: String,
+    pub port: Option<u16>,
+    /// Private key file, the agent and SSH configuration are used without one.
+    pub key: Option<String>,
+}
+
+/// Command prefix which runs a shell command line on the testing host, the
+/// command line is passed as its last argument.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transport {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Transport {
+    pub fn new(program: String, args: Vec<String>) -> Self {
+        Self { program, args }
+    }
+
+    /// Parses a whitespace separated prefix such as `ssh -p 2222 tester@host`,
+    /// or `sh -c` which runs everything locally.
+    pub fn parse(command_line: &str) -> Option<Self> {
+        let mut words = command_line.split_whitespace().map(String::from);
+
+        Some(Self::new(words.next()?, words.collect()))
+    }
+
+    pub fn ssh(settings: &SshSettings) -> Self {
+        // Batch mode fails instead of waiting for a password nobody can type in.
+        let mut args = vec!["-o".to_string(), "BatchMode=yes".to_string()];
+        if let Some(port) = settings.port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if let Some(key) = &settings.key {
+            args.extend(["-i".to_string(), key.clone()]);
+        }
+        args.push(if settings.user.is_empty() {
+            settings.host.clone()
+        } else {
+            format!("{}@{}", settings.user, settings.host)
+        });
+
+        Self::new("ssh".into(), args)
+    }
+
+    /// Command which runs `command_line` on the host.
+    pub fn command(&self, command_line: &str) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).arg(command_line);
+        command
+    }
+}
+
+/// File of a local mode configuration copied to a testing host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadFile {
+    pub local: PathBuf,
+    /// Path relative to the working directory on the host.
+    pub remote: String,
+    pub executable: bool,
+}
+
+/// Files of a local mode configuration which a testing host needs, together
+/// with the configuration of their copies. Missing expected outputs are left
+/// out for trster to report.
+pub fn upload_plan(config: &Config) -> std::io::Result<(Vec<UploadFile>, Config)> {
+    let RunningMode::Local {
+        in_test_path,
+        out_test_path,
+        err_test_path,
+        compiled_program_path,
+    } = config.running_mode()
+    else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "only local mode configurations can be uploaded",
+        ));
+    };
+
+    let mut files = vec![UploadFile {
+        local: compiled_program_path.into(),
+        remote: PROGRAM_FILE.into(),
+        executable: true,
+    }];
+    for name in config.test_names() {
+        for (dir, extension) in [
+            (in_test_path, "in"),
+            (out_test_path, "out"),
+            (err_test_path, "err"),
+        ] {
+            let local = Path::new(dir).join(format!("{name}.{extension}"));
+            if local.exists() {
+                files.push(UploadFile {
+                    local,
+                    remote: format!("{extension}/{name}.{extension}"),
+                    executable: false,
+                });
+            }
+        }
+    }
+
+    let remote = config.clone().with_running_mode(RunningMode::Local {
+        in_test_path: "in".into(),
+        out_test_path: "out".into(),
+        err_test_path: "err".into(),
+        compiled_program_path: format!("./{PROGRAM_FILE}"),
+    });
+
+    Ok((files, remote))
+}
+
+/// Adds a file to an archive extracted in the working directory of a host.
+fn append(
+    archive: &mut tar::Builder<Vec<u8>>,
+    path: &str,
+    content: &[u8],
+    executable: bool,
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(if executable { 0o755 } else { 0o644 });
+
+    archive.append_data(&mut header, path, content)
+}
+
+/// Quotes `word` for a POSIX shell.
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Working directory on a testing host reached through a [`Transport`].
+pub struct RemoteSession {
+    transport: Transport,
+    dir: String,
+}
+
+impl RemoteSession {
+    /// `dir` is relative to the home directory on the host unless it is absolute.
+    pub fn new(transport: Transport, dir: String) -> Self {
+        Self { transport, dir }
+    }
+
+    /// Runs `command_line` in the working directory with `input` on its standard input.
+    fn run(&self, command_line: &str, input: &[u8]) -> std::io::Result<()> {
+        let command_line = format!(
+            "mkdir -p {dir} && cd {dir} && {command_line}",
+            dir = quote(&self.dir)
+        );
+
+        let mut child = self
+            .transport
+            .command(&command_line)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().unwrap();
+
+        // The input is written on the side, a command which fails before
+        // reading all of it would otherwise block on a full error pipe.
+        let (written, output) = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || stdin.write_all(input));
+            let output = child.wait_with_output();
+            (writer.join().unwrap(), output)
+        });
+
+        let output = output?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "remote command failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        written
+    }
+
+    /// Copies a local file to `remote`, a path relative to the working directory.
+    pub fn upload(&self, local: &Path, remote: &str, executable: bool) -> std::io::Result<()> {
+        let content = std::fs::read(local)?;
+
+        let mut command_line = String::new();
+        if let Some(parent) = Path::new(remote)
+            .parent()
+            .and_then(|parent| parent.to_str())
+        {
+            if !parent.is_empty() {
+                command_line.push_str(&format!("mkdir -p {} && ", quote(parent)));
+            }
+        }
+        command_line.push_str(&format!("cat > {}", quote(remote)));
+        if executable {
+            command_line.push_str(&format!(" && chmod +x {}", quote(remote)));
+        }
+
+        self.run(&command_line, &content)
+    }
+
+    /// Uploads the program and tests of a local mode configuration and returns
+    /// the configuration of the uploaded copies. Everything is sent as one tar
+    /// archive to a single remote command.
+    pub fn upload_local(&self, config: &Config) -> std::io::Result<Config> {
+        let (files, remote) = upload_plan(config)?;
+
+        let mut archive = tar::Builder::new(Vec::new());
+        for file in files {
+            let content = std::fs::read(&file.local)?;
+            append(&mut archive, &file.remote, &content, file.executable)?;
+        }
+        append(
+            &mut archive,
+            CONFIG_FILE,
+            &serde_json::to_vec(&remote)?,
+            false,
+        )?;
+        let archive = archive.into_inner()?;
+
+        // Tests without expected error outputs still need the directory.
+        self.run("mkdir -p in out err && tar -xmf -", &archive)?;
+
+        Ok(remote)
+    }
+
+    /// Starts `trster` in the working directory with the uploaded configuration.
+    /// Frames of the backend protocol are read from the standard output of the
+    /// child and control frames are written to its standard input.
+    pub fn spawn_trster(&self, trster: &str) -> std::io::Result<Child> {
+        let command_line = format!(
+            "cd {} && {trster} --format binary --config-file {CONFIG_FILE}",
+            quote(&self.dir)
+        );
+
+        self.transport
+            .command(&command_line)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, os::unix::fs::PermissionsExt};
+
+    use super::*;
+    use crate::{Concurrency, Language};
+
+    #[test]
+    fn ssh_command() {
+        let settings = SshSettings {
+            host: "example.org".into(),
+            user: "tester".into(),
+            port: Some(2222),
+            key: Some("/keys/id".into()),
+        };
+
+        let command = Transport::ssh(&settings).command("echo 'hi'");
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect();
+        assert_eq!(command.get_program(), "ssh");
+        assert_eq!(
+            args,
+            [
+                "-o",
+                "BatchMode=yes",
+                "-p",
+                "2222",
+                "-i",
+                "/keys/id",
+                "tester@example.org",
+                "echo 'hi'"
+            ]
+        );
+    }
+
+    #[test]
+    fn upload_and_spawn_through_local_shell() {
+        let local = tempfile::tempdir().unwrap();
+        let tests = local.path().join("tests");
+        std::fs::create_dir(&tests).unwrap();
+        std::fs::write(tests.join("a b.in"), "1 2\n").unwrap();
+        std::fs::write(tests.join("a b.out"), "3\n").unwrap();
+        let program = local.path().join("prog");
+        std::fs::write(&program, "#!/bin/sh\n").unwrap();
+
+        let tests = tests.to_str().unwrap().to_string();
+        let config = Config::new(
+            RunningMode::Local {
+                in_test_path: tests.clone(),
+                out_test_path: tests.clone(),
+                err_test_path: tests,
+                compiled_program_path: program.to_str().unwrap().into(),
+            },
+            vec!["a b".into()],
+            Language::Cpp,
+            Concurrency::Disabled,
+        );
+
+        let dir = local.path().join("it's remote");
+        let session = RemoteSession::new(
+            Transport::parse("sh -c").unwrap(),
+            dir.to_str().unwrap().into(),
+        );
+        let remote = session.upload_local(&config).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("in/a b.in")).unwrap(),
+            "1 2\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/a b.out")).unwrap(),
+            "3\n"
+        );
+        assert!(!dir.join("err/a b.err").exists());
+        let mode = std::fs::metadata(dir.join(PROGRAM_FILE))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_ne!(mode & 0o111, 0);
+        assert!(matches!(
+            remote.running_mode(),
+            RunningMode::Local { in_test_path, .. } if in_test_path == "in"
+        ));
+
+        let mut child = session.spawn_trster("echo").unwrap();
+        let mut output = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        assert!(child.wait().unwrap().success());
+        assert_eq!(output, "--format binary --config-file config.json\n");
+
+        let session = RemoteSession::new(Transport::parse("false").unwrap(), "x".into());
+        assert!(session.upload(&program, "program", true).is_err());
+    }
+
+    #[test]
+    fn failing_command_keeps_its_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = RemoteSession::new(
+            Transport::parse("sh -c").unwrap(),
+            dir.path().to_str().unwrap().into(),
+        );
+
+        // More input than a pipe holds, none of it is read.
+        let error = session
+            .run("echo broken >&2; exit 3", &vec![0; 1 << 20])
+            .unwrap_err();
+        assert!(error.to_string().contains("broken"), "{error}");
+    }
+}
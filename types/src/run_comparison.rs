@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{RunRecord, TestMeasure, TestResult};
+
+/// How the verdict of a test differs between two runs.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum VerdictChange {
+    /// Only the later run contains the test.
+    Added,
+    /// Only the earlier run contains the test.
+    Removed,
+    NewlyFailing,
+    NewlyPassing,
+    /// Both runs failed, or did not run the test, for different reasons.
+    Changed,
+    Unchanged,
+}
+
+impl VerdictChange {
+    fn between(before: &TestMeasure, after: &TestMeasure) -> Self {
+        let passed = |outcome: &TestMeasure| matches!(outcome, TestMeasure::Success { .. });
+
+        if std::mem::discriminant(before) == std::mem::discriminant(after) {
+            Self::Unchanged
+        } else if passed(before) && after.is_failure() {
+            Self::NewlyFailing
+        } else if before.is_failure() && passed(after) {
+            Self::NewlyPassing
+        } else {
+            Self::Changed
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TestDelta {
+    pub name: String,
+    pub before: Option<TestMeasure>,
+    pub after: Option<TestMeasure>,
+    pub change: VerdictChange,
+    /// Change of the wall time in seconds, when both runs measured it.
+    pub time_delta: Option<f64>,
+    /// Change of the peak memory in bytes, when both runs measured it.
+    pub memory_delta: Option<i64>,
+}
+
+/// Test by test difference of two recorded runs.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RunComparison {
+    /// Tests of the earlier run in its order, followed by the added ones.
+    pub tests: Vec<TestDelta>,
+    /// Program hashes differ, or one of them is unknown.
+    pub program_changed: bool,
+    /// Top level configuration fields whose values differ.
+    pub config_changes: Vec<String>,
+}
+
+impl RunComparison {
+    pub fn new(before: &RunRecord, after: &RunRecord) -> Self {
+        let mut tests: Vec<_> = before
+            .results
+            .iter()
+            .map(|result| Self::delta(Some(result), find(after, result.name())))
+            .collect();
+        tests.extend(
+            after
+                .results
+                .iter()
+                .filter(|result| find(before, result.name()).is_none())
+                .map(|result| Self::delta(None, Some(result))),
+        );
+
+        let program_changed = match (&before.program_hash, &after.program_hash) {
+            (Some(before), Some(after)) => before != after,
+            _ => true,
+        };
+
+        Self {
+            tests,
+            program_changed,
+            config_changes: config_changes(before, after),
+        }
+    }
+
+    fn delta(before: Option<&TestResult>, after: Option<&TestResult>) -> TestDelta {
+        let change = match (before, after) {
+            (Some(before), Some(after)) => {
+                VerdictChange::between(before.outcome(), after.outcome())
+            }
+            (None, _) => VerdictChange::Added,
+            (_, None) => VerdictChange::Removed,
+        };
+
+        let both = before.zip(after);
+        let time_delta = both.and_then(|(before, after)| Some(after.time()? - before.time()?));
+        let memory_delta = both.and_then(|(before, after)| {
+            Some(peak_memory(after)? as i64 - peak_memory(before)? as i64)
+        });
+
+        TestDelta {
+            name: before
+                .or(after)
+                .map(|result| result.name().to_string())
+                .unwrap_or_default(),
+            before: before.map(|result| result.outcome().clone()),
+            after: after.map(|result| result.outcome().clone()),
+            change,
+            time_delta,
+            memory_delta,
+        }
+    }
+
+    /// Tests whose verdict is `change`.
+    pub fn with_change(&self, change: VerdictChange) -> impl Iterator<Item = &TestDelta> {
+        self.tests.iter().filter(move |test| test.change == change)
+    }
+}
+
+fn find<'a>(run: &'a RunRecord, name: &str) -> Option<&'a TestResult> {
+    run.results.iter().find(|result| result.name() == name)
+}
+
+fn peak_memory(result: &TestResult) -> Option<u64> {
+    match (result.timing(), result.outcome()) {
+        (Some(timing), _) => Some(timing.peak_memory),
+        (None, TestMeasure::MemoryLimitExceeded { peak_memory }) => Some(*peak_memory),
+        _ => None,
+    }
+}
+
+fn config_changes(before: &RunRecord, after: &RunRecord) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) = (
+        serde_json::to_value(&before.config),
+        serde_json::to_value(&after.config),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<_> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| before.get(*field) != after.get(*field))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::local_config, Statistics, Timing};
+
+    fn run(list: &[&str], program_hash: &str, results: Vec<TestResult>) -> RunRecord {
+        RunRecord {
+            timestamp: 0,
+            config: local_config(list),
+            program_hash: Some(program_hash.into()),
+            results,
+        }
+    }
+
+    fn passed(name: &str, time: f64, peak_memory: u64) -> TestResult {
+        let statistics = Statistics::from_samples(&[time]).unwrap();
+        crate::test_support::passed(name, time).with_timing(Some(Timing {
+            runs: 1,
+            wall_time: statistics.clone(),
+            cpu_time: statistics,
+            peak_memory,
+        }))
+    }
+
+    #[test]
+    fn comparison() {
+        let before = run(
+            &["a", "b", "c"],
+            "1234",
+            vec![
+                passed("a", 1.0, 100),
+                TestResult::new("b".into(), TestMeasure::Timeout),
+                passed("c", 1.0, 100),
+            ],
+        );
+        let after = run(
+            &["a", "b", "d"],
+            "1234",
+            vec![
+                TestResult::new("a".into(), TestMeasure::OutputLimitExceeded),
+                passed("b", 0.5, 300),
+                passed("d", 1.0, 100),
+            ],
+        );
+
+        let comparison = RunComparison::new(&before, &after);
+        let changes: Vec<_> = comparison
+            .tests
+            .iter()
+            .map(|test| (test.name.as_str(), test.change))
+            .collect();
+
+        assert_eq!(
+            changes,
+            [
+                ("a", VerdictChange::NewlyFailing),
+                ("b", VerdictChange::NewlyPassing),
+                ("c", VerdictChange::Removed),
+                ("d", VerdictChange::Added)
+            ]
+        );
+        assert_eq!(comparison.tests[0].time_delta, None);
+        assert_eq!(comparison.tests[1].time_delta, None);
+        assert!(!comparison.program_changed);
+        assert_eq!(comparison.config_changes, ["test_list"]);
+
+        let faster = run(&["a"], "5678", vec![passed("a", 0.25, 50)]);
+        let comparison = RunComparison::new(&before, &faster);
+        assert_eq!(comparison.tests[0].change, VerdictChange::Unchanged);
+        assert_eq!(comparison.tests[0].time_delta, Some(-0.75));
+        assert_eq!(comparison.tests[0].memory_delta, Some(-50));
+        assert!(comparison.program_changed);
+    }
+}
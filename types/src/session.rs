@@ -0,0 +1,394 @@
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, TcpStream},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    encode_frame, remote::PROGRAM_FILE, upload_plan, BackendMessage, Config, FrameDecoder,
+    FrontendMessage, Language,
+};
+
+/// Requests of a client to `trster serve`, sent as frames on the connection.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum SessionRequest {
+    /// First frame of every connection. Nothing is encrypted, over TCP the
+    /// token is as private as the network it crosses.
+    Authenticate { token: String },
+    /// Writes a file of the session, `path` is relative to its directory.
+    Upload {
+        path: String,
+        content: Vec<u8>,
+        executable: bool,
+    },
+    /// Compiles `source` into `binary`, both relative to the session directory.
+    Compile {
+        language: Language,
+        source: String,
+        binary: String,
+        flags: Vec<String>,
+    },
+    /// Runs tests of a local mode configuration whose paths are relative to
+    /// the session directory.
+    Run { config: Box<Config> },
+    /// Forwarded to the running tests.
+    Control(FrontendMessage),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum SessionResponse {
+    Authenticated,
+    /// The request failed. After a failed authentication the connection is closed.
+    Error {
+        description: String,
+    },
+    Uploaded {
+        path: String,
+    },
+    Compiled {
+        success: bool,
+        log: String,
+    },
+    /// Frame of a run, the run is over after [`BackendMessage::TestingProcessCompleted`].
+    Backend(BackendMessage),
+}
+
+/// Address `trster serve` listens on, `unix:PATH` or any path with a slash is
+/// a Unix socket and everything else a TCP address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionAddress {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl FromStr for SessionAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(Self::Unix(path.into()));
+        }
+        if s.contains('/') {
+            return Ok(Self::Unix(s.into()));
+        }
+        if s.is_empty() {
+            return Err("address is empty".into());
+        }
+
+        Ok(Self::Tcp(s.into()))
+    }
+}
+
+/// Connection to `trster serve`.
+pub enum SessionStream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl SessionStream {
+    pub fn connect(address: &SessionAddress) -> std::io::Result<Self> {
+        match address {
+            SessionAddress::Tcp(address) => TcpStream::connect(address).map(Self::Tcp),
+            SessionAddress::Unix(path) => UnixStream::connect(path).map(Self::Unix),
+        }
+    }
+
+    pub fn try_clone(&self) -> std::io::Result<Self> {
+        match self {
+            Self::Tcp(stream) => stream.try_clone().map(Self::Tcp),
+            Self::Unix(stream) => stream.try_clone().map(Self::Unix),
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.set_read_timeout(timeout),
+            Self::Unix(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+
+    /// Closes the connection for every handle, clones included.
+    pub fn shutdown(&self, how: Shutdown) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.shutdown(how),
+            Self::Unix(stream) => stream.shutdown(how),
+        }
+    }
+}
+
+impl Read for SessionStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.read(buf),
+            Self::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for SessionStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.write(buf),
+            Self::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.flush(),
+            Self::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Authenticated session of `trster serve`, which stays open across runs.
+pub struct SessionClient<S> {
+    stream: S,
+    decoder: FrameDecoder<SessionResponse>,
+}
+
+impl<S: Read + Write> SessionClient<S> {
+    pub fn new(stream: S, token: &str) -> std::io::Result<Self> {
+        let mut client = Self {
+            stream,
+            decoder: FrameDecoder::new(),
+        };
+
+        let token = token.to_string();
+        match client.request(&SessionRequest::Authenticate { token })? {
+            SessionResponse::Authenticated => Ok(client),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn send(&mut self, request: &SessionRequest) -> std::io::Result<()> {
+        self.stream.write_all(&encode_frame(request))?;
+        self.stream.flush()
+    }
+
+    pub fn receive(&mut self) -> std::io::Result<SessionResponse> {
+        let mut chunk = [0; 8192];
+
+        loop {
+            match self.decoder.next_message() {
+                Some(Ok(response)) => return Ok(response),
+                Some(Err(e)) => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                }
+                None => {}
+            }
+
+            let read = self.stream.read(&mut chunk)?;
+            if read == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            self.decoder.push(&chunk[..read]);
+        }
+    }
+
+    fn request(&mut self, request: &SessionRequest) -> std::io::Result<SessionResponse> {
+        self.send(request)?;
+        self.receive()
+    }
+
+    pub fn upload(
+        &mut self,
+        path: &str,
+        content: Vec<u8>,
+        executable: bool,
+    ) -> std::io::Result<()> {
+        let path = path.to_string();
+        match self.request(&SessionRequest::Upload {
+            path,
+            content,
+            executable,
+        })? {
+            SessionResponse::Uploaded { .. } => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Uploads the program and tests of a local mode configuration and returns
+    /// the configuration of the uploaded copies.
+    pub fn upload_local(&mut self, config: &Config) -> std::io::Result<Config> {
+        let (files, remote) = upload_plan(config)?;
+        for file in files {
+            self.upload(&file.remote, std::fs::read(&file.local)?, file.executable)?;
+        }
+
+        Ok(remote)
+    }
+
+    /// Uploads the tests of a local mode configuration with the local file
+    /// `source` in place of its program, and compiles the source on the server.
+    /// Returns the configuration of the uploaded copies and the compiler log.
+    pub fn upload_source(
+        &mut self,
+        config: &Config,
+        source: &Path,
+        language: Language,
+        flags: Vec<String>,
+    ) -> std::io::Result<(Config, String)> {
+        let (files, remote) = upload_plan(config)?;
+        for file in files.iter().filter(|file| file.remote != PROGRAM_FILE) {
+            self.upload(&file.remote, std::fs::read(&file.local)?, file.executable)?;
+        }
+
+        // Compilers tell the language by the extension, so the name is kept.
+        let name = source
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| std::io::Error::other("the source has no file name"))?;
+        let remote_source = format!("source/{name}");
+        self.upload(&remote_source, std::fs::read(source)?, false)?;
+
+        match self.compile(language, &remote_source, PROGRAM_FILE, flags)? {
+            (true, log) => Ok((remote, log)),
+            (false, log) => Err(std::io::Error::other(format!(
+                "compilation failed:\n{}",
+                log.trim()
+            ))),
+        }
+    }
+
+    /// Compiles an uploaded source, returning whether it succeeded and the compiler log.
+    pub fn compile(
+        &mut self,
+        language: Language,
+        source: &str,
+        binary: &str,
+        flags: Vec<String>,
+    ) -> std::io::Result<(bool, String)> {
+        let request = SessionRequest::Compile {
+            language,
+            source: source.into(),
+            binary: binary.into(),
+            flags,
+        };
+
+        match self.request(&request)? {
+            SessionResponse::Compiled { success, log } => Ok((success, log)),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Runs tests of an uploaded configuration and passes every frame of the run to `on_message`.
+    pub fn run(
+        &mut self,
+        config: Config,
+        on_message: impl FnMut(BackendMessage),
+    ) -> std::io::Result<()> {
+        self.start_run(config)?;
+        self.finish_run(on_message)
+    }
+
+    /// Requests a run, control frames sent afterwards reach it.
+    pub fn start_run(&mut self, config: Config) -> std::io::Result<()> {
+        self.send(&SessionRequest::Run {
+            config: Box::new(config),
+        })
+    }
+
+    /// Passes every frame of a started run to `on_message` until it completes.
+    pub fn finish_run(
+        &mut self,
+        mut on_message: impl FnMut(BackendMessage),
+    ) -> std::io::Result<()> {
+        let mut greeted = false;
+
+        loop {
+            match self.receive()? {
+                SessionResponse::Backend(message) => {
+                    // The first frame of every run has to come from a compatible trster.
+                    if !greeted {
+                        message
+                            .clone()
+                            .accept_hello()
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                        greeted = true;
+                    }
+                    let completed = message == BackendMessage::TestingProcessCompleted;
+                    on_message(message);
+                    if completed {
+                        return Ok(());
+                    }
+                }
+                response => return Err(unexpected(response)),
+            }
+        }
+    }
+}
+
+impl SessionClient<SessionStream> {
+    /// Second handle of the connection, which sends [`SessionRequest::Control`]
+    /// frames while [`SessionClient::finish_run`] reads the first one.
+    pub fn control_stream(&self) -> std::io::Result<SessionStream> {
+        self.stream.try_clone()
+    }
+}
+
+fn unexpected(response: SessionResponse) -> std::io::Error {
+    match response {
+        SessionResponse::Error { description } => std::io::Error::other(description),
+        response => std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("unexpected response {response:?}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Concurrency, RunningMode};
+
+    #[test]
+    fn addresses() {
+        assert_eq!(
+            "127.0.0.1:7070".parse(),
+            Ok(SessionAddress::Tcp("127.0.0.1:7070".into()))
+        );
+        assert_eq!(
+            "unix:trster.sock".parse(),
+            Ok(SessionAddress::Unix("trster.sock".into()))
+        );
+        assert_eq!(
+            "/run/trster.sock".parse(),
+            Ok(SessionAddress::Unix("/run/trster.sock".into()))
+        );
+        assert!("".parse::<SessionAddress>().is_err());
+    }
+
+    #[test]
+    fn run_request_round_trip() {
+        let config = Config::new(
+            RunningMode::Local {
+                in_test_path: "in".into(),
+                out_test_path: "out".into(),
+                err_test_path: "err".into(),
+                compiled_program_path: "./program".into(),
+            },
+            vec!["a".into()],
+            Language::Cpp,
+            Concurrency::Auto { reserve: 1 },
+        );
+
+        let mut decoder = FrameDecoder::<SessionRequest>::new();
+        decoder.push(&encode_frame(&SessionRequest::Run {
+            config: Box::new(config),
+        }));
+        let Some(Ok(SessionRequest::Run { config })) = decoder.next_message() else {
+            panic!("run request was not decoded");
+        };
+        assert_eq!(config.test_names().collect::<Vec<_>>(), ["a"]);
+        assert_eq!(
+            *config.concurrency_settings(),
+            Concurrency::Auto { reserve: 1 }
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! Fixtures shared by the tests of several modules.
+
+use crate::{Concurrency, Config, Language, RunningMode, TestMeasure, TestResult};
+
+/// Passing result of the test `name` which took `time` seconds.
+pub fn passed(name: &str, time: f64) -> TestResult {
+    TestResult::new(
+        name.into(),
+        TestMeasure::Success {
+            time,
+            exit_status: Some(0),
+        },
+    )
+}
+
+/// Local mode configuration of the tests `test_list`, its paths are never read.
+pub fn local_config(test_list: &[&str]) -> Config {
+    Config::new(
+        RunningMode::Local {
+            in_test_path: "t".into(),
+            out_test_path: "t".into(),
+            err_test_path: "t".into(),
+            compiled_program_path: "p".into(),
+        },
+        test_list.iter().map(|name| name.to_string()).collect(),
+        Language::Cpp,
+        Concurrency::Disabled,
+    )
+}